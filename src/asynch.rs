@@ -0,0 +1,344 @@
+use crate::calibration::Calibration;
+use crate::config::{self, DeviceConfig, SPIMode};
+use crate::interface::AsyncInterface;
+use crate::measurements::{self, Acceleration, AngularRate, MagneticField, RawSample};
+use crate::registers::{self, Register};
+use crate::{Component, Lsm9ds1Error, DEVICE_ID_AG, DEVICE_ID_M};
+
+/// An LSM9DS1 sensor, accessed through an asynchronous interface.
+///
+/// Only a subset of the `Lsm9ds1` API is supported: initialization, enabling the sensors,
+/// sampling rates, identification, reset, and reading measurements and temperature. Readings
+/// have the stored `Calibration` applied. FIFO, interrupts, status, configuration read-back,
+/// self-tests, the hard-iron offset registers and the calibration routines are only available
+/// on the blocking driver; settings for them can still be applied through the builder.
+pub struct Lsm9ds1Async<I: AsyncInterface> {
+    pub(crate) interface: I,
+    pub(crate) config: DeviceConfig,
+    pub(crate) calibration: Calibration,
+}
+
+impl<I: AsyncInterface> Lsm9ds1Async<I> {
+    /// Initialize the device by applying all settings.
+    pub async fn init(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let mut config = self.config.clone();
+        if let Some(spi_mode) = self.interface.spi_mode() {
+            config.spi_mode = spi_mode;
+        }
+
+        for (reg, value) in config.all_registers().into_iter() {
+            self.interface.write(reg, value).await?;
+        }
+
+        self.config = config;
+
+        Ok(())
+    }
+
+    /// Calibration applied to the sensor readings.
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Replace the calibration applied to the sensor readings, e.g. with a stored one.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Enable or disable the accelerometer.
+    pub async fn set_accelerometer_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_5_xl = registers::ctrl_reg_5_xl(
+            self.config.accel_gyro.accel_decimation,
+            enabled,
+            enabled,
+            enabled,
+        );
+
+        self.interface
+            .write(Register::CTRL_REG5_XL, ctrl_reg_5_xl)
+            .await?;
+
+        self.config.accel_gyro.accel_x_axis_enabled = enabled;
+        self.config.accel_gyro.accel_y_axis_enabled = enabled;
+        self.config.accel_gyro.accel_z_axis_enabled = enabled;
+
+        Ok(())
+    }
+
+    /// Enable or disable the gyroscope.
+    pub async fn set_gyroscope_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_4 = registers::ctrl_reg_4(
+            enabled,
+            enabled,
+            enabled,
+            self.config.accel_gyro.accel_interrupt_latched,
+        );
+
+        self.interface
+            .write(Register::CTRL_REG4, ctrl_reg_4)
+            .await?;
+
+        self.config.accel_gyro.gyro_x_axis_enabled = enabled;
+        self.config.accel_gyro.gyro_y_axis_enabled = enabled;
+        self.config.accel_gyro.gyro_z_axis_enabled = enabled;
+
+        Ok(())
+    }
+
+    /// Enable or disable the magnetometer.
+    pub async fn set_magnetometer_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let operating_mode = if enabled {
+            config::magnetometer::OperatingMode::ContinuousConversion
+        } else {
+            config::magnetometer::OperatingMode::PowerDown
+        };
+
+        let ctrl_reg_3_m = registers::ctrl_rg_3_m(
+            self.config.magnetometer.i2c_disabled,
+            self.config.magnetometer.low_power_mode,
+            self.config
+                .magnetometer
+                .effective_spi_write_only(self.config.spi_mode),
+            operating_mode,
+        );
+
+        self.interface
+            .write(Register::CTRL_REG3_M, ctrl_reg_3_m)
+            .await?;
+
+        self.config.magnetometer.operating_mode = operating_mode;
+
+        Ok(())
+    }
+
+    /// Set the sampling rate for the accelerometer and gyroscope.
+    pub async fn set_accel_gyro_sampling_rate(
+        &mut self,
+        rate: config::accel_gyro::AccelGyroSamplingRate,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_1_g = registers::ctrl_reg_1_g(rate, self.config.accel_gyro.gyro_full_scale);
+
+        self.interface
+            .write(Register::CTRL_REG1_G, ctrl_reg_1_g)
+            .await?;
+
+        self.config.accel_gyro.accel_gyro_sampling_rate = rate;
+
+        Ok(())
+    }
+
+    /// Set the sampling rate for the accelerometer (if gyro is disabled).
+    pub async fn set_accel_sampling_rate(
+        &mut self,
+        rate: config::accel_gyro::AccelSamplingRate,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_6_xl = registers::ctrl_reg_6_xl(
+            rate,
+            self.config.accel_gyro.accel_full_scale,
+            self.config.accel_gyro.accel_bandwidth,
+        );
+
+        self.interface
+            .write(Register::CTRL_REG6_XL, ctrl_reg_6_xl)
+            .await?;
+
+        self.config.accel_gyro.accel_only_sampling_rate = rate;
+
+        Ok(())
+    }
+
+    /// Read out the raw gyroscope sample.
+    pub async fn read_gyroscope_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
+        let mut data = [0; 6];
+        self.interface
+            .read_multiple(Register::OUT_X_L_G, &mut data)
+            .await?;
+
+        Ok(RawSample::from_le_bytes(data))
+    }
+
+    /// Read out the angular rate measured by the gyroscope, with the gyroscope calibration
+    /// applied. A temperature dependent bias model additionally reads out the temperature.
+    pub async fn read_angular_rate(&mut self) -> Result<AngularRate, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_gyroscope_raw().await?;
+        let angular_rate =
+            AngularRate::from_raw(raw, self.config.accel_gyro.gyro_full_scale.sensitivity());
+
+        Ok(
+            match (self.calibration.gyro_temperature, self.calibration.gyro) {
+                (Some(model), _) => model.apply(angular_rate, self.temperature_c().await?),
+                (None, Some(calibration)) => calibration.apply(angular_rate),
+                (None, None) => angular_rate,
+            },
+        )
+    }
+
+    /// Read out gyroscope data in dps.
+    pub async fn get_gyroscope_data(
+        &mut self,
+    ) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let angular_rate = self.read_angular_rate().await?;
+
+        Ok((angular_rate.x, angular_rate.y, angular_rate.z))
+    }
+
+    /// Read out the raw accelerometer sample.
+    pub async fn read_accelerometer_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
+        let mut data = [0; 6];
+        self.interface
+            .read_multiple(Register::OUT_X_L_XL, &mut data)
+            .await?;
+
+        Ok(RawSample::from_le_bytes(data))
+    }
+
+    /// Read out the acceleration measured by the accelerometer, with the accelerometer
+    /// calibration applied.
+    pub async fn read_acceleration(&mut self) -> Result<Acceleration, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_accelerometer_raw().await?;
+        let acceleration =
+            Acceleration::from_raw(raw, self.config.accel_gyro.accel_full_scale.sensitivity());
+
+        Ok(match self.calibration.accel {
+            Some(calibration) => calibration.apply(acceleration),
+            None => acceleration,
+        })
+    }
+
+    /// Read out accelerometer data in g.
+    pub async fn get_accelerometer_data(
+        &mut self,
+    ) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let acceleration = self.read_acceleration().await?;
+
+        Ok((acceleration.x, acceleration.y, acceleration.z))
+    }
+
+    /// Read out the raw magnetometer sample.
+    pub async fn read_magnetometer_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
+        let mut data = [0; 6];
+        self.interface
+            .read_multiple(Register::OUT_X_L_M, &mut data)
+            .await?;
+
+        Ok(RawSample::from_le_bytes(data))
+    }
+
+    /// Read out the magnetic field measured by the magnetometer, with the magnetometer
+    /// calibration applied.
+    pub async fn read_magnetic_field(
+        &mut self,
+    ) -> Result<MagneticField, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_magnetometer_raw().await?;
+        let field = MagneticField::from_raw(raw, self.config.magnetometer.full_scale.sensitivity());
+
+        Ok(match self.calibration.mag {
+            Some(calibration) => calibration.apply(field),
+            None => field,
+        })
+    }
+
+    /// Read out magnetometer data in Gauss.
+    pub async fn get_magnetometer_data(
+        &mut self,
+    ) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let magnetic_field = self.read_magnetic_field().await?;
+
+        Ok((magnetic_field.x, magnetic_field.y, magnetic_field.z))
+    }
+
+    /// Read out chip identification for the accelerometer and gyroscope.
+    pub async fn who_am_i_ag(&mut self) -> Result<u8, Lsm9ds1Error<I::BusError>> {
+        self.interface.read(Register::WHO_AM_I).await
+    }
+
+    /// Read out chip identification for the magnetometer.
+    pub async fn who_am_i_m(&mut self) -> Result<u8, Lsm9ds1Error<I::BusError>> {
+        self.interface.read(Register::WHO_AM_I_M).await
+    }
+
+    /// Switch both components to 3-wire SPI mode if the interface uses it, as the sensor doesn't
+    /// drive the shared data line before. Other settings are left at their power-on values.
+    pub(crate) async fn select_spi_mode(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        if let Some(SPIMode::ThreeWire) = self.interface.spi_mode() {
+            for (reg, value) in DeviceConfig::spi_mode_registers(SPIMode::ThreeWire) {
+                self.interface.write(reg, value).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the chip identification of both components. On a 3-wire SPI interface, the device
+    /// must have been initialized first.
+    pub async fn verify_identity(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let found = self.who_am_i_ag().await?;
+        if found != DEVICE_ID_AG {
+            return Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::AccelGyro,
+                found,
+            });
+        }
+
+        let found = self.who_am_i_m().await?;
+        if found != DEVICE_ID_M {
+            return Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::Magnetometer,
+                found,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Apply software reset.
+    pub async fn reset(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let mut ctrl_reg = self.interface.read(Register::CTRL_REG8).await?;
+        ctrl_reg |= 0b1;
+        self.interface.write(Register::CTRL_REG8, ctrl_reg).await
+    }
+
+    /// Read out the raw temperature sample.
+    pub async fn read_temperature_raw(&mut self) -> Result<i16, Lsm9ds1Error<I::BusError>> {
+        let temp_l = self.interface.read(Register::OUT_TEMP_L).await?;
+        let temp_h = self.interface.read(Register::OUT_TEMP_H).await?;
+
+        Ok(i16::from_le_bytes([temp_l, temp_h]))
+    }
+
+    /// Read out temperature in °C. Temperature is used for internal temperature compensation and
+    /// not as a primary sensor output. Values will be inaccurate if primary sensor types aren't
+    /// being sampled.
+    pub async fn temperature_c(&mut self) -> Result<f32, Lsm9ds1Error<I::BusError>> {
+        let temp = self.read_temperature_raw().await?;
+
+        Ok(measurements::temperature_c_from_raw(temp))
+    }
+}
+
+#[cfg(test)]
+use crate::{interface::i2c, interface::I2cInterface, tests::block_on, tests::dummy_i2c::DummyI2c};
+
+#[test]
+fn init_and_read_async() {
+    let i2c = DummyI2c::new_with_tx(&[0x00, 0x40]);
+    let interface = I2cInterface::new(i2c, i2c::Config::default());
+
+    let mut device = block_on(crate::Lsm9ds1Builder::new().init_on_async(interface))
+        .expect("Error during initialization");
+
+    let (x, y, z) = block_on(device.get_accelerometer_data()).expect("Error reading data");
+
+    for value in [x, y, z] {
+        assert!((value - 1.0).abs() < 1e-3, "Wrong acceleration {value}");
+    }
+}
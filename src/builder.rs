@@ -1,15 +1,23 @@
+use crate::calibration::Calibration;
 use crate::config::{
-    accel_gyro::{AccelGyroSamplingRate, AccelSamplingRate, GyroFullScale},
-    magnetometer, DeviceConfig,
+    accel_gyro::{AccelFullScale, AccelGyroSamplingRate, AccelSamplingRate, GyroFullScale},
+    magnetometer, DeviceConfig, SPIMode,
 };
+#[cfg(feature = "async")]
+use crate::interface::AsyncInterface;
 use crate::interface::Interface;
+use crate::measurements::MagneticField;
 use crate::Lsm9ds1;
+#[cfg(feature = "async")]
+use crate::Lsm9ds1Async;
 use crate::Lsm9ds1Error;
 
 /// Builder for LSM9DS1 sensor.
 #[derive(Default)]
 pub struct Lsm9ds1Builder {
     config: DeviceConfig,
+    verify_identity: bool,
+    calibration: Calibration,
 }
 
 impl Lsm9ds1Builder {
@@ -18,20 +26,66 @@ impl Lsm9ds1Builder {
     }
 
     pub fn with_config(&mut self, config: DeviceConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            verify_identity: self.verify_identity,
+            calibration: self.calibration,
+        }
     }
 
-    pub fn init_on<I: Interface>(self, interface: I) -> Result<Lsm9ds1<I>, Lsm9ds1Error> {
+    pub fn init_on<I: Interface>(
+        self,
+        interface: I,
+    ) -> Result<Lsm9ds1<I>, Lsm9ds1Error<I::BusError>> {
         let mut device = Lsm9ds1::<I> {
             interface,
             config: self.config,
+            calibration: self.calibration,
         };
 
+        if self.verify_identity {
+            device.select_spi_mode()?;
+            device.verify_identity()?;
+        }
         device.init()?;
 
         Ok(device)
     }
 
+    #[cfg(feature = "async")]
+    pub async fn init_on_async<I: AsyncInterface>(
+        self,
+        interface: I,
+    ) -> Result<Lsm9ds1Async<I>, Lsm9ds1Error<I::BusError>> {
+        let mut device = Lsm9ds1Async::<I> {
+            interface,
+            config: self.config,
+            calibration: self.calibration,
+        };
+
+        if self.verify_identity {
+            device.select_spi_mode().await?;
+            device.verify_identity().await?;
+        }
+        device.init().await?;
+
+        Ok(device)
+    }
+
+    /// Check the chip identification of both components before initialization, and fail with
+    /// `Lsm9ds1Error::WrongDeviceId` on mismatch. On a 3-wire SPI interface, both components are
+    /// switched to 3-wire mode first.
+    pub fn with_identity_check(mut self, enabled: bool) -> Self {
+        self.verify_identity = enabled;
+        self
+    }
+
+    /// Apply a calibration, e.g. a stored one, to the sensor readings.
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
     pub fn with_accelerometer_enabled(mut self, enabled: bool) -> Self {
         self.config.accel_gyro.accel_x_axis_enabled = enabled;
         self.config.accel_gyro.accel_y_axis_enabled = enabled;
@@ -70,8 +124,8 @@ impl Lsm9ds1Builder {
         self
     }
 
-    pub fn with_accelerometer_scale(mut self, scale: magnetometer::FullScale) -> Self {
-        self.config.magnetometer.full_scale = scale;
+    pub fn with_accelerometer_scale(mut self, scale: AccelFullScale) -> Self {
+        self.config.accel_gyro.accel_full_scale = scale;
         self
     }
 
@@ -84,4 +138,14 @@ impl Lsm9ds1Builder {
         self.config.magnetometer.full_scale = scale;
         self
     }
+
+    pub fn with_hard_iron_offset(mut self, offset: MagneticField) -> Self {
+        self.config.magnetometer.hard_iron_offset = offset;
+        self
+    }
+
+    pub fn with_spi_mode(mut self, mode: SPIMode) -> Self {
+        self.config.spi_mode = mode;
+        self
+    }
 }
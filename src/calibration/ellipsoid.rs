@@ -0,0 +1,348 @@
+use crate::calibration::{solve, MagCalibration};
+use crate::measurements::MagneticField;
+
+/// Number of parameters of the ellipsoid
+/// `a·x² + b·y² + c·z² + 2d·xy + 2e·xz + 2f·yz + 2g·x + 2h·y + 2i·z = 1`.
+const PARAMETERS: usize = 9;
+
+/// Maximum number of Jacobi sweeps, three rotations each. A 3x3 matrix converges in far fewer.
+const MAX_JACOBI_SWEEPS: usize = 32;
+
+/// Off-diagonal magnitude below which a Jacobi rotation is skipped, relative to the diagonal.
+const JACOBI_TOLERANCE: f64 = 1e-15;
+
+/// Least squares fit of an ellipsoid to magnetometer samples, to determine hard- and soft-iron
+/// distortion. Samples are accumulated into the normal equations as they arrive, so the fitter
+/// neither allocates nor keeps the samples. The device should be rotated through as many
+/// orientations as possible while feeding samples, until the fit reports good coverage.
+///
+/// Samples must not have a `MagCalibration` applied, but may have the hard-iron offset of the
+/// magnetometer applied, in which case the fitted offset is relative to it.
+#[derive(Clone, Debug)]
+pub struct EllipsoidFitter {
+    /// Sum of the outer products of the sample terms.
+    normal: [[f64; PARAMETERS]; PARAMETERS],
+    /// Sum of the sample terms.
+    rhs: [f64; PARAMETERS],
+    count: u32,
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+/// Result of an ellipsoid fit.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EllipsoidFit {
+    /// Hard- and soft-iron correction.
+    pub calibration: MagCalibration,
+    /// Strength of the corrected field, in gauss.
+    pub field_strength: f32,
+    /// RMS deviation of the sample magnitudes from the fitted ellipsoid, relative to the field
+    /// strength. Values of a few percent indicate noisy samples or a changing field.
+    pub residual: f32,
+    /// Fraction of the ellipsoid extent covered by the samples along the X, Y and Z axis, from
+    /// 0.0 to 1.0. Keep rotating the device until all axes are close to 1.0.
+    pub coverage: [f32; 3],
+}
+
+impl Default for EllipsoidFitter {
+    fn default() -> Self {
+        Self {
+            normal: [[0.0; PARAMETERS]; PARAMETERS],
+            rhs: [0.0; PARAMETERS],
+            count: 0,
+            min: [f32::MAX; 3],
+            max: [f32::MIN; 3],
+        }
+    }
+}
+
+impl EllipsoidFitter {
+    /// Create a fitter without any samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a magnetometer sample.
+    pub fn push(&mut self, sample: MagneticField) {
+        let [x, y, z] = [sample.x as f64, sample.y as f64, sample.z as f64];
+        let terms = [
+            x * x,
+            y * y,
+            z * z,
+            2.0 * x * y,
+            2.0 * x * z,
+            2.0 * y * z,
+            2.0 * x,
+            2.0 * y,
+            2.0 * z,
+        ];
+
+        for (row, &term) in self.normal.iter_mut().zip(&terms) {
+            for (sum, &other) in row.iter_mut().zip(&terms) {
+                *sum += term * other;
+            }
+        }
+        for (sum, term) in self.rhs.iter_mut().zip(terms) {
+            *sum += term;
+        }
+
+        for (axis, value) in [sample.x, sample.y, sample.z].into_iter().enumerate() {
+            self.min[axis] = self.min[axis].min(value);
+            self.max[axis] = self.max[axis].max(value);
+        }
+        self.count += 1;
+    }
+
+    /// Number of samples added.
+    pub fn sample_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Fit an ellipsoid to the samples added so far. Returns `None` if the samples don't
+    /// determine an ellipsoid, e.g. because there are too few or they lie in a plane.
+    pub fn fit(&self) -> Option<EllipsoidFit> {
+        let p = solve(self.normal, self.rhs)?;
+
+        let q = [[p[0], p[3], p[4]], [p[3], p[1], p[5]], [p[4], p[5], p[2]]];
+        let linear = [p[6], p[7], p[8]];
+
+        // Completing the square gives (m - center)ᵀ·Q·(m - center) = 1 + centerᵀ·Q·center.
+        let q_inverse = invert(&q)?;
+        let center = multiply(&q_inverse, linear).map(|value| -value);
+        let scale = 1.0 + dot(center, multiply(&q, center));
+        if scale <= 0.0 {
+            return None;
+        }
+
+        let shape = q.map(|row| row.map(|value| value / scale));
+        let (eigenvalues, eigenvectors) = eigen(shape);
+        if eigenvalues.iter().any(|&value| value <= 0.0) {
+            return None;
+        }
+
+        // Map the ellipsoid onto a sphere with the mean semi-axis length as radius.
+        let inverse_semi_axes = eigenvalues.map(sqrt);
+        let field_strength = inverse_semi_axes
+            .iter()
+            .map(|value| 1.0 / value)
+            .sum::<f64>()
+            / 3.0;
+
+        let mut soft_iron = [[0.0; 3]; 3];
+        for (row, values) in soft_iron.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|k| eigenvectors[row][k] * inverse_semi_axes[k] * eigenvectors[col][k])
+                    .sum::<f64>()
+                    * field_strength;
+            }
+        }
+
+        // Σ(pᵀ·terms - 1)² expanded in terms of the accumulated sums.
+        let squared_error =
+            dot(p, multiply(&self.normal, p)) - 2.0 * dot(p, self.rhs) + self.count as f64;
+        // The algebraic error is r²/R² - 1 ≈ 2·(r - R)/R for a sample at radius r.
+        let residual = sqrt(squared_error.max(0.0) / self.count as f64) / (2.0 * scale);
+
+        let shape_inverse = q_inverse.map(|row| row.map(|value| value * scale));
+        let mut coverage = [0.0; 3];
+        for (axis, coverage) in coverage.iter_mut().enumerate() {
+            let extent = 2.0 * sqrt(shape_inverse[axis][axis]);
+            let covered = (self.max[axis] - self.min[axis]) as f64;
+            *coverage = (covered / extent).min(1.0) as f32;
+        }
+
+        Some(EllipsoidFit {
+            calibration: MagCalibration {
+                hard_iron: MagneticField {
+                    x: center[0] as f32,
+                    y: center[1] as f32,
+                    z: center[2] as f32,
+                },
+                soft_iron: soft_iron.map(|row| row.map(|value| value as f32)),
+            },
+            field_strength: field_strength as f32,
+            residual: residual as f32,
+            coverage,
+        })
+    }
+}
+
+/// Invert a 3x3 matrix.
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let mut inverse = [[0.0; 3]; 3];
+    for col in 0..3 {
+        let mut unit = [0.0; 3];
+        unit[col] = 1.0;
+        let column = solve(*m, unit)?;
+        for (row, value) in column.into_iter().enumerate() {
+            inverse[row][col] = value;
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Multiply a matrix with a vector.
+fn multiply<const N: usize>(matrix: &[[f64; N]; N], vector: [f64; N]) -> [f64; N] {
+    matrix.map(|row| dot(row, vector))
+}
+
+/// Dot product of two vectors.
+fn dot<const N: usize>(a: [f64; N], b: [f64; N]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Eigen-decomposition of a symmetric 3x3 matrix using cyclic Jacobi rotations. Returns the
+/// eigenvalues and a matrix with the corresponding eigenvectors as columns.
+fn eigen(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    for _ in 0..MAX_JACOBI_SWEEPS {
+        let mut rotated = false;
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q].abs() <= JACOBI_TOLERANCE * (a[p][p].abs() + a[q][q].abs()) {
+                continue;
+            }
+            rotated = true;
+
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + sqrt(theta * theta + 1.0));
+            let c = 1.0 / sqrt(t * t + 1.0);
+            let s = t * c;
+
+            // a = Jᵀ·a·J and v = v·J, with the rotation J in the (p, q) plane. The columns of a and v
+            // are rotated first, then the rows of a.
+            for row in a.iter_mut().chain(v.iter_mut()) {
+                let (xp, xq) = (row[p], row[q]);
+                row[p] = c * xp - s * xq;
+                row[q] = s * xp + c * xq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = core::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = core::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+        }
+
+        if !rotated {
+            break;
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+/// Square root by Newton's method, which `core` doesn't provide.
+fn sqrt(value: f64) -> f64 {
+    if value <= 0.0 || value.is_infinite() {
+        return value.max(0.0);
+    }
+
+    // Halving the exponent gives a starting point within a factor of two.
+    let mut root = f64::from_bits((value.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..6 {
+        root = 0.5 * (root + value / root);
+    }
+
+    root
+}
+
+#[cfg(test)]
+fn sphere_samples(fraction: f32) -> impl Iterator<Item = [f32; 3]> {
+    use core::f32::consts::PI;
+
+    (0..12).flat_map(move |lat| {
+        let polar = PI * fraction * (lat as f32 + 0.5) / 12.0;
+        (0..24).map(move |lon| {
+            let azimuth = 2.0 * PI * lon as f32 / 24.0;
+            [
+                polar.sin() * azimuth.cos(),
+                polar.sin() * azimuth.sin(),
+                polar.cos(),
+            ]
+        })
+    })
+}
+
+#[test]
+fn math_helpers() {
+    for value in [0.0, 1e-9, 0.25, 2.0, 1e12] {
+        assert!(
+            (sqrt(value) - value.sqrt()).abs() <= 1e-12 * value.sqrt(),
+            "Wrong root of {value}"
+        );
+    }
+
+    let (values, vectors) = eigen([[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 5.0]]);
+    let mut sorted = values;
+    sorted.sort_by(f64::total_cmp);
+    for (value, expected) in sorted.into_iter().zip([1.0, 3.0, 5.0]) {
+        assert!((value - expected).abs() < 1e-12, "Wrong eigenvalue {value}");
+    }
+    assert!(
+        (vectors[0][2].abs() + vectors[1][2].abs()) < 1e-12,
+        "Wrong eigenvector"
+    );
+}
+
+#[test]
+fn ellipsoid_fit() {
+    // Soft-iron distortion and hard-iron offset applied to a 0.5 gauss field.
+    let distortion = [[1.2, 0.1, 0.0], [0.1, 0.9, -0.05], [0.0, -0.05, 1.05]];
+    let offset = [0.3, -0.2, 0.1];
+    let distort = |direction: [f32; 3]| {
+        let [x, y, z] = direction.map(|value| value * 0.5);
+        MagneticField {
+            x: distortion[0][0] * x + distortion[0][1] * y + distortion[0][2] * z + offset[0],
+            y: distortion[1][0] * x + distortion[1][1] * y + distortion[1][2] * z + offset[1],
+            z: distortion[2][0] * x + distortion[2][1] * y + distortion[2][2] * z + offset[2],
+        }
+    };
+
+    let mut fitter = EllipsoidFitter::new();
+    assert!(fitter.fit().is_none(), "Fit without samples");
+    for direction in sphere_samples(1.0) {
+        fitter.push(distort(direction));
+    }
+    assert_eq!(fitter.sample_count(), 288, "Wrong sample count");
+
+    let fit = fitter.fit().expect("No fit");
+    let center = fit.calibration.hard_iron;
+    for (value, expected) in [center.x, center.y, center.z].into_iter().zip(offset) {
+        assert!((value - expected).abs() < 1e-4, "Wrong offset {value}");
+    }
+    assert!(fit.residual < 1e-4, "Residual {} too large", fit.residual);
+    assert!(
+        fit.coverage.iter().all(|&coverage| coverage > 0.95),
+        "Low coverage {:?}",
+        fit.coverage
+    );
+
+    for direction in sphere_samples(1.0).step_by(7) {
+        let corrected = fit.calibration.apply(distort(direction));
+        let magnitude =
+            (corrected.x * corrected.x + corrected.y * corrected.y + corrected.z * corrected.z)
+                .sqrt();
+        assert!(
+            (magnitude - fit.field_strength).abs() < 1e-3,
+            "Distortion not corrected"
+        );
+    }
+
+    let mut fitter = EllipsoidFitter::new();
+    for direction in sphere_samples(0.3) {
+        fitter.push(distort(direction));
+    }
+    let fit = fitter.fit().expect("No fit");
+    assert!(fit.coverage[2] < 0.5, "Partial rotation fully covered");
+}
+
+#[test]
+fn planar_samples_rejected() {
+    let mut fitter = EllipsoidFitter::new();
+    for [x, y, _] in sphere_samples(1.0) {
+        fitter.push(MagneticField { x, y, z: 0.2 });
+    }
+
+    assert!(fitter.fit().is_none(), "Fit to planar samples");
+}
@@ -0,0 +1,456 @@
+use crate::measurements::{Acceleration, AngularRate, MagneticField};
+use temperature::GyroTemperatureModel;
+
+pub mod ellipsoid;
+pub mod storage;
+pub mod temperature;
+
+/// Default number of samples collected for a gyroscope calibration.
+pub const GYRO_CALIBRATION_SAMPLES: u16 = 128;
+
+/// Default limit of the per-axis gyroscope output variance in dps² during a calibration. Noise of
+/// a stationary device stays well below, even at ±2000dps.
+pub const GYRO_CALIBRATION_VARIANCE_LIMIT: f32 = 0.5;
+
+/// Default number of samples averaged for each orientation of an accelerometer calibration.
+pub const ACCEL_CALIBRATION_SAMPLES: u16 = 64;
+
+/// Default limit of the per-axis accelerometer output variance in g² while recording an
+/// orientation, which allows for about 10mg of noise.
+pub const ACCEL_CALIBRATION_VARIANCE_LIMIT: f32 = 1e-4;
+
+/// Smallest magnitude of the determinant of the accelerometer sensitivity matrix that is
+/// inverted. A plausible matrix is close to the identity with a determinant close to one.
+const MIN_DETERMINANT: f32 = 1e-3;
+
+/// Smallest pivot accepted while solving the normal equations of a fit, relative to the largest
+/// diagonal element. Smaller pivots mean the samples don't determine the fit, e.g. because
+/// magnetometer samples lie in a plane.
+const MIN_RELATIVE_PIVOT: f64 = 1e-12;
+
+/// 3x3 matrix, as rows.
+pub type Matrix3 = [[f32; 3]; 3];
+
+/// 3x3 identity matrix.
+const IDENTITY: Matrix3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Calibration applied by the driver to every sensor reading.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Calibration {
+    /// Gyroscope calibration, if any.
+    pub gyro: Option<GyroCalibration>,
+    /// Temperature dependent gyroscope bias, if any. Takes precedence over `gyro`.
+    pub gyro_temperature: Option<GyroTemperatureModel>,
+    /// Accelerometer calibration, if any.
+    pub accel: Option<AccelCalibration>,
+    /// Magnetometer calibration, if any.
+    pub mag: Option<MagCalibration>,
+}
+
+/// Gyroscope zero-rate bias, determined while the device is stationary.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GyroCalibration {
+    /// Angular rate measured at rest, in dps.
+    pub bias: AngularRate,
+}
+
+impl GyroCalibration {
+    /// Remove the bias from an angular rate.
+    pub fn apply(&self, rate: AngularRate) -> AngularRate {
+        AngularRate {
+            x: rate.x - self.bias.x,
+            y: rate.y - self.bias.y,
+            z: rate.z - self.bias.z,
+        }
+    }
+}
+
+/// Accelerometer offset, scale and cross-axis misalignment correction. A reading `a` is
+/// corrected to `matrix * (a - bias)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AccelCalibration {
+    /// Correction matrix, the inverse of the measured sensitivity matrix.
+    pub matrix: Matrix3,
+    /// Acceleration measured at zero g, in g.
+    pub bias: Acceleration,
+}
+
+impl Default for AccelCalibration {
+    fn default() -> Self {
+        Self {
+            matrix: IDENTITY,
+            bias: Acceleration::default(),
+        }
+    }
+}
+
+impl AccelCalibration {
+    /// Correct an acceleration.
+    pub fn apply(&self, acceleration: Acceleration) -> Acceleration {
+        let [x, y, z] = multiply(
+            &self.matrix,
+            [
+                acceleration.x - self.bias.x,
+                acceleration.y - self.bias.y,
+                acceleration.z - self.bias.z,
+            ],
+        );
+
+        Acceleration { x, y, z }
+    }
+}
+
+/// Orientation of the device during an accelerometer calibration, named after the axis pointing
+/// up, which then measures +1g.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+    ZUp,
+    ZDown,
+}
+
+impl Orientation {
+    /// All orientations, in the order they are requested by `AccelCalibrator::next_missing`.
+    pub const ALL: [Orientation; 6] = [
+        Self::ZUp,
+        Self::ZDown,
+        Self::XUp,
+        Self::XDown,
+        Self::YUp,
+        Self::YDown,
+    ];
+
+    /// Axis pointing up or down, as index into `[x, y, z]`.
+    fn axis(self) -> usize {
+        match self {
+            Self::XUp | Self::XDown => 0,
+            Self::YUp | Self::YDown => 1,
+            Self::ZUp | Self::ZDown => 2,
+        }
+    }
+
+    /// The axis points up.
+    fn up(self) -> bool {
+        matches!(self, Self::XUp | Self::YUp | Self::ZUp)
+    }
+
+    /// Index into the recorded readings, `[x_up, x_down, y_up, ...]`.
+    fn index(self) -> usize {
+        self.axis() * 2 + usize::from(!self.up())
+    }
+}
+
+/// Six-orientation accelerometer calibration. Record the averaged acceleration with each axis
+/// pointing up and down once, e.g. using `Lsm9ds1::average_acceleration`, then solve for the
+/// calibration.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AccelCalibrator {
+    readings: [Option<[f32; 3]>; 6],
+}
+
+impl AccelCalibrator {
+    /// Create a calibrator without any recorded orientations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the averaged acceleration for an orientation, replacing an earlier recording.
+    /// Returns `false` and ignores the reading if gravity isn't measured mostly along the
+    /// expected axis, i.e. the device wasn't placed as requested.
+    pub fn record(&mut self, orientation: Orientation, acceleration: Acceleration) -> bool {
+        let reading = [acceleration.x, acceleration.y, acceleration.z];
+        let axis = orientation.axis();
+        let value = if orientation.up() {
+            reading[axis]
+        } else {
+            -reading[axis]
+        };
+
+        let placed = reading
+            .iter()
+            .enumerate()
+            .all(|(other, component)| other == axis || component.abs() < value);
+        if placed {
+            self.readings[orientation.index()] = Some(reading);
+        }
+
+        placed
+    }
+
+    /// The next orientation to record, or `None` once all are recorded.
+    pub fn next_missing(&self) -> Option<Orientation> {
+        Orientation::ALL
+            .into_iter()
+            .find(|orientation| self.readings[orientation.index()].is_none())
+    }
+
+    /// Solve for the offset, scale and misalignment of each axis. The bias is the mean of the
+    /// up and down readings, and each column of the sensitivity matrix half their difference.
+    /// Returns `None` if an orientation is missing or the sensitivity matrix can't be inverted.
+    pub fn solve(&self) -> Option<AccelCalibration> {
+        let mut bias = [0.0; 3];
+        let mut sensitivity = [[0.0; 3]; 3];
+
+        for axis in 0..3 {
+            let up = self.readings[axis * 2]?;
+            let down = self.readings[axis * 2 + 1]?;
+
+            for (row, (bias, sensitivity)) in bias.iter_mut().zip(&mut sensitivity).enumerate() {
+                *bias += (up[row] + down[row]) / 6.0;
+                sensitivity[axis] = (up[row] - down[row]) / 2.0;
+            }
+        }
+
+        let [x, y, z] = bias;
+        Some(AccelCalibration {
+            matrix: invert(&sensitivity)?,
+            bias: Acceleration { x, y, z },
+        })
+    }
+}
+
+/// Magnetometer hard- and soft-iron correction, e.g. from an `EllipsoidFitter`. A reading `m` is
+/// corrected to `soft_iron * (m - hard_iron)`. Unlike the hard-iron offset of the magnetometer,
+/// this is applied by the driver.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MagCalibration {
+    /// Offset caused by magnetized materials near the sensor, in gauss.
+    pub hard_iron: MagneticField,
+    /// Correction of the field distortion caused by soft magnetic materials near the sensor.
+    pub soft_iron: Matrix3,
+}
+
+impl Default for MagCalibration {
+    fn default() -> Self {
+        Self {
+            hard_iron: MagneticField::default(),
+            soft_iron: IDENTITY,
+        }
+    }
+}
+
+impl MagCalibration {
+    /// Correct a magnetic field.
+    pub fn apply(&self, field: MagneticField) -> MagneticField {
+        let [x, y, z] = multiply(
+            &self.soft_iron,
+            [
+                field.x - self.hard_iron.x,
+                field.y - self.hard_iron.y,
+                field.z - self.hard_iron.z,
+            ],
+        );
+
+        MagneticField { x, y, z }
+    }
+}
+
+/// Multiply a matrix with a vector.
+fn multiply(matrix: &Matrix3, vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+/// Invert a matrix using its adjugate, or `None` if it is (close to) singular.
+fn invert(m: &Matrix3) -> Option<Matrix3> {
+    let cofactor = |row: usize, col: usize| {
+        let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+        let (c0, c1) = ((col + 1) % 3, (col + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+
+    let determinant =
+        m[0][0] * cofactor(0, 0) + m[0][1] * cofactor(0, 1) + m[0][2] * cofactor(0, 2);
+    if determinant.abs() < MIN_DETERMINANT {
+        return None;
+    }
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (row, values) in inverse.iter_mut().enumerate() {
+        for (col, value) in values.iter_mut().enumerate() {
+            *value = cofactor(col, row) / determinant;
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Solve the linear system `a·x = b` by Gaussian elimination with partial pivoting.
+fn solve<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+    let scale = (0..N).map(|i| a[i][i].abs()).fold(0.0, f64::max);
+    if scale == 0.0 {
+        return None;
+    }
+
+    for col in 0..N {
+        let pivot = (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < scale * MIN_RELATIVE_PIVOT {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (pivot_rows, rows) = a.split_at_mut(col + 1);
+        let pivot_row = &pivot_rows[col];
+        let (pivot_rhs, rhs) = b.split_at_mut(col + 1);
+        for (row, rhs) in rows.iter_mut().zip(rhs) {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            *rhs -= factor * pivot_rhs[col];
+        }
+    }
+
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let sum: f64 = (row + 1..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+
+    Some(x)
+}
+
+/// Streaming per-axis mean and variance of `[x, y, z]` samples (Welford's algorithm).
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct AxisStatistics {
+    count: u32,
+    mean: [f32; 3],
+    sum_of_squares: [f32; 3],
+}
+
+impl AxisStatistics {
+    /// Add a sample.
+    pub(crate) fn push(&mut self, sample: [f32; 3]) {
+        self.count += 1;
+
+        for ((mean, sum_of_squares), value) in self
+            .mean
+            .iter_mut()
+            .zip(self.sum_of_squares.iter_mut())
+            .zip(sample)
+        {
+            let delta = value - *mean;
+            *mean += delta / self.count as f32;
+            *sum_of_squares += delta * (value - *mean);
+        }
+    }
+
+    /// Mean of each axis.
+    pub(crate) fn mean(&self) -> [f32; 3] {
+        self.mean
+    }
+
+    /// Population variance of each axis.
+    pub(crate) fn variance(&self) -> [f32; 3] {
+        if self.count == 0 {
+            return [0.0; 3];
+        }
+
+        self.sum_of_squares.map(|sum| sum / self.count as f32)
+    }
+}
+
+#[test]
+fn gyro_bias_removed() {
+    let calibration = GyroCalibration {
+        bias: AngularRate {
+            x: 1.0,
+            y: -0.5,
+            z: 0.25,
+        },
+    };
+
+    assert_eq!(
+        calibration.apply(AngularRate {
+            x: 1.0,
+            y: 0.0,
+            z: 10.0
+        }),
+        AngularRate {
+            x: 0.0,
+            y: 0.5,
+            z: 9.75
+        },
+        "Wrong calibrated rate"
+    );
+}
+
+#[test]
+fn axis_statistics() {
+    let mut statistics = AxisStatistics::default();
+    for sample in [[1.0, 0.0, -2.0], [3.0, 0.0, -2.0], [2.0, 0.0, -2.0]] {
+        statistics.push(sample);
+    }
+
+    assert_eq!(statistics.mean(), [2.0, 0.0, -2.0], "Wrong mean");
+
+    let variance = statistics.variance();
+    assert!((variance[0] - 2.0 / 3.0).abs() < 1e-6, "Wrong X variance");
+    assert_eq!(variance[1..], [0.0, 0.0], "Wrong Y/Z variance");
+}
+
+#[test]
+fn six_position_accel_calibration() {
+    let sensitivity = [
+        [1.02, 0.01, -0.02],
+        [0.0, 0.98, 0.015],
+        [0.01, -0.005, 1.01],
+    ];
+    let bias = [0.04, -0.03, 0.02];
+    let measure = |gravity: [f32; 3]| {
+        let [x, y, z] = multiply(&sensitivity, gravity);
+        Acceleration {
+            x: x + bias[0],
+            y: y + bias[1],
+            z: z + bias[2],
+        }
+    };
+
+    let mut calibrator = AccelCalibrator::new();
+    assert_eq!(calibrator.solve(), None, "Solved without readings");
+
+    while let Some(orientation) = calibrator.next_missing() {
+        let mut gravity = [0.0; 3];
+        gravity[orientation.axis()] = if orientation.up() { 1.0 } else { -1.0 };
+        assert!(
+            calibrator.record(orientation, measure(gravity)),
+            "Reading rejected for {orientation:?}"
+        );
+    }
+
+    let calibration = calibrator.solve().expect("No calibration");
+    assert!((calibration.bias.x - 0.04).abs() < 1e-6, "Wrong bias");
+
+    let corrected = calibration.apply(measure([0.6, -0.8, 0.0]));
+    for (value, expected) in [corrected.x, corrected.y, corrected.z]
+        .into_iter()
+        .zip([0.6, -0.8, 0.0])
+    {
+        assert!((value - expected).abs() < 1e-5, "Wrong correction {value}");
+    }
+}
+
+#[test]
+fn accel_orientation_checked() {
+    let mut calibrator = AccelCalibrator::new();
+    let z_up = Acceleration {
+        x: 0.1,
+        y: 0.0,
+        z: 1.0,
+    };
+
+    assert!(
+        !calibrator.record(Orientation::ZDown, z_up),
+        "Z up accepted"
+    );
+    assert!(!calibrator.record(Orientation::XUp, z_up), "Z up accepted");
+    assert!(calibrator.record(Orientation::ZUp, z_up), "Z up rejected");
+    assert_eq!(
+        calibrator.next_missing(),
+        Some(Orientation::ZDown),
+        "Wrong next orientation"
+    );
+}
@@ -0,0 +1,401 @@
+use core::fmt::Display;
+
+use crate::calibration::temperature::GyroTemperatureModel;
+use crate::calibration::{AccelCalibration, Calibration, GyroCalibration, MagCalibration};
+use crate::measurements::{Acceleration, AngularRate, MagneticField};
+
+/// Identifies stored calibration data.
+const MAGIC: [u8; 4] = *b"L9DC";
+
+/// Version of the stored layout.
+pub const CALIBRATION_VERSION: u8 = 2;
+
+/// Size of the stored calibration in bytes.
+///
+/// | Offset | Size | Content                                                               |
+/// |--------|------|-----------------------------------------------------------------------|
+/// | 0      | 4    | Magic `"L9DC"`                                                        |
+/// | 4      | 1    | Layout version                                                        |
+/// | 5      | 1    | Present calibrations: bit 0 gyro, bit 1 accel, bit 2 mag, bit 3 gyro  |
+/// |        |      | temperature model                                                     |
+/// | 6      | 2    | Reserved, zero                                                        |
+/// | 8      | 4    | Device ID                                                             |
+/// | 12     | 12   | Gyro bias x, y, z                                                     |
+/// | 24     | 36   | Accel matrix, row by row                                              |
+/// | 60     | 12   | Accel bias x, y, z                                                    |
+/// | 72     | 12   | Mag hard-iron x, y, z                                                 |
+/// | 84     | 36   | Mag soft-iron matrix, row by row                                      |
+/// | 120    | 4    | Gyro temperature model reference temperature                          |
+/// | 124    | 36   | Gyro temperature model offset, slope and curvature x, y, z            |
+/// | 160    | 4    | CRC-32 of all preceding bytes                                         |
+///
+/// All values are little endian, calibration values are `f32`. Absent calibrations are stored
+/// as zeros. Version 1 ends with the checksum at offset 120, without the temperature model.
+pub const CALIBRATION_BYTES: usize = 164;
+
+/// Size of the stored calibration in bytes for version 1 of the layout.
+const CALIBRATION_BYTES_V1: usize = 124;
+
+const GYRO_PRESENT: u8 = 0b001;
+const ACCEL_PRESENT: u8 = 0b010;
+const MAG_PRESENT: u8 = 0b100;
+const GYRO_TEMPERATURE_PRESENT: u8 = 0b1000;
+
+/// Errors while restoring a stored calibration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StorageError {
+    /// Fewer bytes than the layout requires.
+    Truncated,
+    /// The data doesn't start with the magic bytes, e.g. because nothing was stored yet.
+    BadMagic,
+    /// The layout version isn't supported.
+    UnsupportedVersion(u8),
+    /// The checksum doesn't match the data.
+    ChecksumMismatch,
+    /// The calibration was stored for another device.
+    WrongDeviceId {
+        /// Device ID found in the data.
+        found: u32,
+    },
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "Stored calibration truncated"),
+            Self::BadMagic => write!(f, "No stored calibration"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported calibration version {}", version)
+            }
+            Self::ChecksumMismatch => write!(f, "Stored calibration corrupted"),
+            Self::WrongDeviceId { found } => {
+                write!(f, "Calibration stored for device {:#010x}", found)
+            }
+        }
+    }
+}
+
+impl core::error::Error for StorageError {}
+
+impl Calibration {
+    /// Serialize the calibration for non-volatile storage, tagged with an application defined
+    /// device ID, e.g. a board serial number.
+    pub fn to_bytes(&self, device_id: u32) -> [u8; CALIBRATION_BYTES] {
+        let mut bytes = [0; CALIBRATION_BYTES];
+        let mut writer = Writer {
+            bytes: &mut bytes,
+            offset: 0,
+        };
+
+        let flags = (self.gyro.is_some() as u8 * GYRO_PRESENT)
+            | (self.accel.is_some() as u8 * ACCEL_PRESENT)
+            | (self.mag.is_some() as u8 * MAG_PRESENT)
+            | (self.gyro_temperature.is_some() as u8 * GYRO_TEMPERATURE_PRESENT);
+
+        writer.put(&MAGIC);
+        writer.put(&[CALIBRATION_VERSION, flags, 0, 0]);
+        writer.put(&device_id.to_le_bytes());
+
+        let gyro = self.gyro.unwrap_or_default();
+        writer.put_vector([gyro.bias.x, gyro.bias.y, gyro.bias.z]);
+
+        let accel = self.accel.unwrap_or(ZERO_ACCEL);
+        for row in accel.matrix {
+            writer.put_vector(row);
+        }
+        writer.put_vector([accel.bias.x, accel.bias.y, accel.bias.z]);
+
+        let mag = self.mag.unwrap_or(ZERO_MAG);
+        writer.put_vector([mag.hard_iron.x, mag.hard_iron.y, mag.hard_iron.z]);
+        for row in mag.soft_iron {
+            writer.put_vector(row);
+        }
+
+        let model = self.gyro_temperature.unwrap_or_default();
+        writer.put(&model.reference_c.to_le_bytes());
+        for rate in [model.offset, model.slope, model.curvature] {
+            writer.put_vector([rate.x, rate.y, rate.z]);
+        }
+
+        let checksum_offset = CALIBRATION_BYTES - 4;
+        let checksum = crc32(&bytes[..checksum_offset]);
+        bytes[checksum_offset..].copy_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    /// Restore a calibration serialized with `to_bytes` by this or an earlier version of the
+    /// layout, checking that it was stored for `device_id`. Bytes following the stored
+    /// calibration are ignored.
+    pub fn from_bytes(bytes: &[u8], device_id: u32) -> Result<Self, StorageError> {
+        let header = bytes.get(..5).ok_or(StorageError::Truncated)?;
+        if header[..4] != MAGIC {
+            return Err(StorageError::BadMagic);
+        }
+
+        let version = header[4];
+        let size = match version {
+            1 => CALIBRATION_BYTES_V1,
+            CALIBRATION_VERSION => CALIBRATION_BYTES,
+            _ => return Err(StorageError::UnsupportedVersion(version)),
+        };
+        let bytes = bytes.get(..size).ok_or(StorageError::Truncated)?;
+
+        let mut reader = Reader { bytes, offset: 5 };
+        let [flags, _, _] = reader.take();
+        let found = u32::from_le_bytes(reader.take());
+
+        let checksum_offset = size - 4;
+        let checksum = u32::from_le_bytes(
+            Reader {
+                bytes,
+                offset: checksum_offset,
+            }
+            .take(),
+        );
+        if crc32(&bytes[..checksum_offset]) != checksum {
+            return Err(StorageError::ChecksumMismatch);
+        }
+        if found != device_id {
+            return Err(StorageError::WrongDeviceId { found });
+        }
+
+        let [x, y, z] = reader.take_vector();
+        let gyro = GyroCalibration {
+            bias: AngularRate { x, y, z },
+        };
+
+        let matrix = [
+            reader.take_vector(),
+            reader.take_vector(),
+            reader.take_vector(),
+        ];
+        let [x, y, z] = reader.take_vector();
+        let accel = AccelCalibration {
+            matrix,
+            bias: Acceleration { x, y, z },
+        };
+
+        let [x, y, z] = reader.take_vector();
+        let soft_iron = [
+            reader.take_vector(),
+            reader.take_vector(),
+            reader.take_vector(),
+        ];
+        let mag = MagCalibration {
+            hard_iron: MagneticField { x, y, z },
+            soft_iron,
+        };
+
+        let gyro_temperature = if version >= 2 {
+            let reference_c = f32::from_le_bytes(reader.take());
+            let [offset, slope, curvature] = [(); 3].map(|_| {
+                let [x, y, z] = reader.take_vector();
+                AngularRate { x, y, z }
+            });
+
+            Some(GyroTemperatureModel {
+                reference_c,
+                offset,
+                slope,
+                curvature,
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            gyro: (flags & GYRO_PRESENT != 0).then_some(gyro),
+            gyro_temperature: gyro_temperature.filter(|_| flags & GYRO_TEMPERATURE_PRESENT != 0),
+            accel: (flags & ACCEL_PRESENT != 0).then_some(accel),
+            mag: (flags & MAG_PRESENT != 0).then_some(mag),
+        })
+    }
+}
+
+/// Stored in place of an absent accelerometer calibration.
+const ZERO_ACCEL: AccelCalibration = AccelCalibration {
+    matrix: [[0.0; 3]; 3],
+    bias: Acceleration {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+};
+
+/// Stored in place of an absent magnetometer calibration.
+const ZERO_MAG: MagCalibration = MagCalibration {
+    hard_iron: MagneticField {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+    soft_iron: [[0.0; 3]; 3],
+};
+
+/// Sequential writer into the stored layout.
+struct Writer<'a> {
+    bytes: &'a mut [u8],
+    offset: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, data: &[u8]) {
+        self.bytes[self.offset..self.offset + data.len()].copy_from_slice(data);
+        self.offset += data.len();
+    }
+
+    fn put_vector(&mut self, vector: [f32; 3]) {
+        for value in vector {
+            self.put(&value.to_le_bytes());
+        }
+    }
+}
+
+/// Sequential reader from the stored layout, which has already been checked for length.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let mut data = [0; N];
+        data.copy_from_slice(&self.bytes[self.offset..self.offset + N]);
+        self.offset += N;
+        data
+    }
+
+    fn take_vector(&mut self) -> [f32; 3] {
+        [(); 3].map(|_| f32::from_le_bytes(self.take()))
+    }
+}
+
+/// CRC-32 (IEEE 802.3), as used by Ethernet and zip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[test]
+fn checksum() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926, "Wrong CRC-32");
+}
+
+#[test]
+fn calibration_round_trip() {
+    let calibration = Calibration {
+        gyro: Some(GyroCalibration {
+            bias: AngularRate {
+                x: 0.5,
+                y: -1.25,
+                z: 0.01,
+            },
+        }),
+        gyro_temperature: Some(GyroTemperatureModel {
+            reference_c: 25.0,
+            offset: AngularRate {
+                x: 0.5,
+                y: 0.0,
+                z: -0.1,
+            },
+            slope: AngularRate {
+                x: 0.01,
+                y: 0.02,
+                z: 0.03,
+            },
+            curvature: AngularRate::default(),
+        }),
+        accel: None,
+        mag: Some(MagCalibration {
+            hard_iron: MagneticField {
+                x: 0.3,
+                y: -0.2,
+                z: 0.1,
+            },
+            soft_iron: [[1.1, 0.01, 0.0], [0.01, 0.95, -0.02], [0.0, -0.02, 1.0]],
+        }),
+    };
+
+    let bytes = calibration.to_bytes(0x1234_5678);
+    assert_eq!(&bytes[..4], b"L9DC", "Wrong magic");
+    assert_eq!(bytes[5], 0b1101, "Wrong presence flags");
+    assert_eq!(
+        Calibration::from_bytes(&bytes, 0x1234_5678),
+        Ok(calibration),
+        "Calibration not restored"
+    );
+
+    let mut page = [0xff; 256];
+    page[..CALIBRATION_BYTES].copy_from_slice(&bytes);
+    assert_eq!(
+        Calibration::from_bytes(&page, 0x1234_5678),
+        Ok(calibration),
+        "Calibration not restored from a flash page"
+    );
+}
+
+#[test]
+fn invalid_calibration_rejected() {
+    let bytes = Calibration::default().to_bytes(7);
+
+    assert_eq!(
+        Calibration::from_bytes(&bytes[..CALIBRATION_BYTES - 1], 7),
+        Err(StorageError::Truncated)
+    );
+    assert_eq!(
+        Calibration::from_bytes(&[0xff; CALIBRATION_BYTES], 7),
+        Err(StorageError::BadMagic)
+    );
+    assert_eq!(
+        Calibration::from_bytes(&bytes, 8),
+        Err(StorageError::WrongDeviceId { found: 7 })
+    );
+
+    let mut corrupted = bytes;
+    corrupted[4] = 3;
+    assert_eq!(
+        Calibration::from_bytes(&corrupted, 7),
+        Err(StorageError::UnsupportedVersion(3))
+    );
+
+    let mut corrupted = bytes;
+    corrupted[30] ^= 0x01;
+    assert_eq!(
+        Calibration::from_bytes(&corrupted, 7),
+        Err(StorageError::ChecksumMismatch)
+    );
+}
+
+#[test]
+fn version_1_restored() {
+    let calibration = Calibration {
+        gyro: Some(GyroCalibration {
+            bias: AngularRate {
+                x: 0.5,
+                y: -1.25,
+                z: 0.01,
+            },
+        }),
+        ..Default::default()
+    };
+
+    let mut bytes = [0; CALIBRATION_BYTES_V1];
+    bytes[..CALIBRATION_BYTES_V1 - 4].copy_from_slice(&calibration.to_bytes(7)[..120]);
+    bytes[4] = 1;
+    let checksum = crc32(&bytes[..CALIBRATION_BYTES_V1 - 4]);
+    bytes[CALIBRATION_BYTES_V1 - 4..].copy_from_slice(&checksum.to_le_bytes());
+
+    assert_eq!(
+        Calibration::from_bytes(&bytes, 7),
+        Ok(calibration),
+        "Version 1 calibration not restored"
+    );
+}
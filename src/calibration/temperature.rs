@@ -0,0 +1,237 @@
+use crate::calibration::solve;
+use crate::measurements::AngularRate;
+
+/// Temperature the gyroscope bias model is centered on, in °C.
+pub const GYRO_MODEL_REFERENCE_C: f32 = 25.0;
+
+/// Degree of the polynomial fitted to the gyroscope bias.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ModelOrder {
+    Linear,
+    Quadratic,
+}
+
+/// Gyroscope zero-rate bias as a function of the die temperature `t`, per axis:
+/// `offset + slope·(t - reference_c) + curvature·(t - reference_c)²`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GyroTemperatureModel {
+    /// Temperature the model is centered on, in °C.
+    pub reference_c: f32,
+    /// Bias at the reference temperature, in dps.
+    pub offset: AngularRate,
+    /// Linear bias change, in dps/°C.
+    pub slope: AngularRate,
+    /// Quadratic bias change, in dps/°C². Zero for a linear model.
+    pub curvature: AngularRate,
+}
+
+impl GyroTemperatureModel {
+    /// Bias at a temperature.
+    pub fn bias_at(&self, temperature_c: f32) -> AngularRate {
+        let t = temperature_c - self.reference_c;
+        let bias = |offset: f32, slope: f32, curvature: f32| offset + (slope + curvature * t) * t;
+
+        AngularRate {
+            x: bias(self.offset.x, self.slope.x, self.curvature.x),
+            y: bias(self.offset.y, self.slope.y, self.curvature.y),
+            z: bias(self.offset.z, self.slope.z, self.curvature.z),
+        }
+    }
+
+    /// Remove the bias at a temperature from an angular rate.
+    pub fn apply(&self, rate: AngularRate, temperature_c: f32) -> AngularRate {
+        let bias = self.bias_at(temperature_c);
+
+        AngularRate {
+            x: rate.x - bias.x,
+            y: rate.y - bias.y,
+            z: rate.z - bias.z,
+        }
+    }
+}
+
+/// Least squares fit of the gyroscope bias against temperature. Record the bias at rest at
+/// several temperatures during a thermal sweep, e.g. using `Lsm9ds1::record_gyro_bias`, then fit
+/// the model. The model is only reliable within the temperature span of the sweep. Samples are
+/// accumulated into the normal equations, so the fitter doesn't keep them.
+#[derive(Clone, Debug)]
+pub struct GyroTemperatureFitter {
+    order: ModelOrder,
+    /// Sums of the powers 0 to 4 of the temperature relative to the reference.
+    powers: [f64; 5],
+    /// Sums of the bias times the powers 0 to 2 of the relative temperature, per axis.
+    weighted: [[f64; 3]; 3],
+    min_c: f32,
+    max_c: f32,
+}
+
+impl GyroTemperatureFitter {
+    /// Create a fitter for a model of the given order, without any samples.
+    pub fn new(order: ModelOrder) -> Self {
+        Self {
+            order,
+            powers: [0.0; 5],
+            weighted: [[0.0; 3]; 3],
+            min_c: f32::MAX,
+            max_c: f32::MIN,
+        }
+    }
+
+    /// Add the bias measured at rest at a temperature.
+    pub fn push(&mut self, temperature_c: f32, bias: AngularRate) {
+        let t = (temperature_c - GYRO_MODEL_REFERENCE_C) as f64;
+
+        let mut power = 1.0;
+        for sum in self.powers.iter_mut() {
+            *sum += power;
+            power *= t;
+        }
+
+        for (weighted, value) in self.weighted.iter_mut().zip([bias.x, bias.y, bias.z]) {
+            let mut power = value as f64;
+            for sum in weighted.iter_mut() {
+                *sum += power;
+                power *= t;
+            }
+        }
+
+        self.min_c = self.min_c.min(temperature_c);
+        self.max_c = self.max_c.max(temperature_c);
+    }
+
+    /// Number of samples added.
+    pub fn sample_count(&self) -> u32 {
+        self.powers[0] as u32
+    }
+
+    /// Temperature span of the samples added so far in °C, to tell when the sweep is wide
+    /// enough.
+    pub fn temperature_span(&self) -> f32 {
+        (self.max_c - self.min_c).max(0.0)
+    }
+
+    /// Fit the model to the samples added so far. Returns `None` if the samples don't determine
+    /// the model, i.e. if there are fewer distinct temperatures than coefficients.
+    pub fn fit(&self) -> Option<GyroTemperatureModel> {
+        let p = &self.powers;
+        let mut normal = [[p[0], p[1], p[2]], [p[1], p[2], p[3]], [p[2], p[3], p[4]]];
+
+        // A linear fit solves the same system with the quadratic coefficient fixed to zero.
+        let linear = self.order == ModelOrder::Linear;
+        if linear {
+            normal[2] = [0.0, 0.0, 1.0];
+            normal[0][2] = 0.0;
+            normal[1][2] = 0.0;
+        }
+
+        let mut coefficients = [[0.0; 3]; 3];
+        for (coefficients, weighted) in coefficients.iter_mut().zip(self.weighted) {
+            let mut rhs = weighted;
+            if linear {
+                rhs[2] = 0.0;
+            }
+
+            *coefficients = solve(normal, rhs)?.map(|value| value as f32);
+        }
+
+        let [x, y, z] = coefficients;
+        Some(GyroTemperatureModel {
+            reference_c: GYRO_MODEL_REFERENCE_C,
+            offset: AngularRate {
+                x: x[0],
+                y: y[0],
+                z: z[0],
+            },
+            slope: AngularRate {
+                x: x[1],
+                y: y[1],
+                z: z[1],
+            },
+            curvature: AngularRate {
+                x: x[2],
+                y: y[2],
+                z: z[2],
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+fn close(a: AngularRate, b: AngularRate) -> bool {
+    [a.x - b.x, a.y - b.y, a.z - b.z]
+        .iter()
+        .all(|difference| difference.abs() < 1e-4)
+}
+
+#[test]
+fn temperature_model() {
+    let model = GyroTemperatureModel {
+        reference_c: 25.0,
+        offset: AngularRate {
+            x: 0.5,
+            y: -0.2,
+            z: 0.0,
+        },
+        slope: AngularRate {
+            x: 0.02,
+            y: 0.0,
+            z: -0.05,
+        },
+        curvature: AngularRate {
+            x: 0.0,
+            y: 0.001,
+            z: 0.0,
+        },
+    };
+
+    let bias = model.bias_at(35.0);
+    assert!(
+        close(
+            bias,
+            AngularRate {
+                x: 0.7,
+                y: -0.1,
+                z: -0.5
+            }
+        ),
+        "Wrong bias {bias:?}"
+    );
+    assert!(
+        close(model.apply(bias, 35.0), AngularRate::default()),
+        "Bias not removed"
+    );
+}
+
+#[test]
+fn temperature_fit() {
+    let bias = |t: f32| {
+        let t = t - 25.0;
+        AngularRate {
+            x: 0.5 + 0.03 * t,
+            y: -1.0 - 0.01 * t + 0.002 * t * t,
+            z: 0.1,
+        }
+    };
+
+    let mut linear = GyroTemperatureFitter::new(ModelOrder::Linear);
+    let mut quadratic = GyroTemperatureFitter::new(ModelOrder::Quadratic);
+    quadratic.push(10.0, bias(10.0));
+    quadratic.push(10.0, bias(10.0));
+    assert!(quadratic.fit().is_none(), "Fit without temperature change");
+
+    for t in (-20..=60).step_by(5) {
+        linear.push(t as f32, bias(t as f32));
+        quadratic.push(t as f32, bias(t as f32));
+    }
+    assert_eq!(linear.sample_count(), 17, "Wrong sample count");
+    assert_eq!(linear.temperature_span(), 80.0, "Wrong temperature span");
+
+    let model = quadratic.fit().expect("No quadratic fit");
+    for t in [-20.0, 0.0, 42.0, 60.0] {
+        assert!(close(model.bias_at(t), bias(t)), "Wrong bias at {t}°C");
+    }
+
+    let model = linear.fit().expect("No linear fit");
+    assert_eq!(model.curvature, AngularRate::default(), "Curvature fitted");
+    assert!((model.slope.x - 0.03).abs() < 1e-5, "Wrong X slope");
+}
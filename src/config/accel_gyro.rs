@@ -1,7 +1,7 @@
-use super::Endianness;
+use super::{ConfigDifferences, Endianness};
 use crate::registers::{self, Register};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelGyroSamplingRate {
     PowerDown,
     _14p9Hz,
@@ -12,7 +12,7 @@ pub enum AccelGyroSamplingRate {
     _952Hz,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelSamplingRate {
     PowerDown,
     _10Hz,
@@ -26,7 +26,7 @@ pub enum AccelSamplingRate {
 // TODO: Gyro bandwidth selection
 // TODO: High pass cutoff
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum GyroFullScale {
     _245dps,
     _500dps,
@@ -43,7 +43,18 @@ impl From<GyroFullScale> for f32 {
     }
 }
 
-#[derive(Copy, Clone)]
+impl GyroFullScale {
+    /// Sensitivity in dps/LSB, as specified in the datasheet.
+    pub fn sensitivity(self) -> f32 {
+        match self {
+            Self::_245dps => 0.00875,
+            Self::_500dps => 0.0175,
+            Self::_2000dps => 0.07,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelFullScale {
     PlusMinus2g,
     PlusMinus4g,
@@ -62,7 +73,19 @@ impl From<AccelFullScale> for f32 {
     }
 }
 
-#[derive(Copy, Clone)]
+impl AccelFullScale {
+    /// Sensitivity in g/LSB, as specified in the datasheet.
+    pub fn sensitivity(self) -> f32 {
+        match self {
+            Self::PlusMinus2g => 0.000061,
+            Self::PlusMinus4g => 0.000122,
+            Self::PlutMinus8g => 0.000244,
+            Self::PlusMinus16g => 0.000732,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelDecimation {
     None,
     Half,
@@ -70,7 +93,7 @@ pub enum AccelDecimation {
     Eighth,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelBandWidth {
     Auto,
     _408Hz,
@@ -79,7 +102,7 @@ pub enum AccelBandWidth {
     _50Hz,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AccelLowpassCutoff {
     _ODRby9,
     _ODRBy50,
@@ -88,6 +111,7 @@ pub enum AccelLowpassCutoff {
 }
 
 /// Configuration of Accelerometer/Gyroscope component.
+#[derive(Clone, Debug, PartialEq)]
 pub struct AccelGyroConfig {
     /// Sampling rate if both accelerometer and gyroscope are active.
     pub accel_gyro_sampling_rate: AccelGyroSamplingRate,
@@ -107,6 +131,8 @@ pub struct AccelGyroConfig {
     pub gyro_y_axis_enabled: bool,
     /// Enable Y-Axis gyroscope data.
     pub gyro_z_axis_enabled: bool,
+    /// Latch accelerometer interrupt generator requests until the source register is read.
+    pub accel_interrupt_latched: bool,
     /// Decimation of accelerometer data.
     pub accel_decimation: AccelDecimation,
     /// Enable X-Axis accelerometer data.
@@ -125,6 +151,10 @@ pub struct AccelGyroConfig {
     pub accel_low_pass_cutoff: AccelLowpassCutoff,
     /// Enable internal digital filter (high pass and low pass) for accelerometer.
     pub accel_filter_enabled: bool,
+    /// Enable gyroscope self-test.
+    pub gyro_self_test: bool,
+    /// Enable accelerometer self-test.
+    pub accel_self_test: bool,
     /// Endianness data output
     pub(super) endianness: Endianness,
 }
@@ -141,6 +171,7 @@ impl Default for AccelGyroConfig {
             gyro_x_axis_enabled: true,
             gyro_y_axis_enabled: true,
             gyro_z_axis_enabled: true,
+            accel_interrupt_latched: false,
             accel_decimation: AccelDecimation::None,
             accel_x_axis_enabled: true,
             accel_y_axis_enabled: true,
@@ -150,13 +181,15 @@ impl Default for AccelGyroConfig {
             accel_high_resolution: false,
             accel_low_pass_cutoff: AccelLowpassCutoff::_ODRBy50,
             accel_filter_enabled: false,
+            gyro_self_test: false,
+            accel_self_test: false,
             endianness: Endianness::BigEndian,
         }
     }
 }
 
 impl AccelGyroConfig {
-    pub fn all_registers(&self) -> [(Register, u8); 7] {
+    pub fn all_registers(&self) -> [(Register, u8); 8] {
         let ctrl_reg_1_g =
             registers::ctrl_reg_1_g(self.accel_gyro_sampling_rate, self.gyro_full_scale);
         let ctrl_reg_2_g =
@@ -167,6 +200,7 @@ impl AccelGyroConfig {
             self.gyro_x_axis_enabled,
             self.gyro_y_axis_enabled,
             self.gyro_z_axis_enabled,
+            self.accel_interrupt_latched,
         );
         let ctrl_reg_5_xl = registers::ctrl_reg_5_xl(
             self.accel_decimation,
@@ -184,6 +218,7 @@ impl AccelGyroConfig {
             self.accel_low_pass_cutoff,
             self.accel_filter_enabled,
         );
+        let ctrl_reg_10 = registers::ctrl_reg_10(self.gyro_self_test, self.accel_self_test);
 
         [
             (Register::CTRL_REG1_G, ctrl_reg_1_g),
@@ -193,6 +228,140 @@ impl AccelGyroConfig {
             (Register::CTRL_REG5_XL, ctrl_reg_5_xl),
             (Register::CTRL_REG6_XL, ctrl_reg_6_xl),
             (Register::CTRL_REG7_XL, ctrl_reg_7_xl),
+            (Register::CTRL_REG10, ctrl_reg_10),
         ]
     }
+
+    /// Rebuild the configuration from register values, in the order of `all_registers()`. The
+    /// endianness is part of CTRL_REG8 and left at its default. Returns `None` if a register holds
+    /// a reserved value.
+    pub(crate) fn from_registers(values: [u8; 8]) -> Option<Self> {
+        let [ctrl_reg_1_g, ctrl_reg_2_g, ctrl_reg_3_g, ctrl_reg_4, ctrl_reg_5_xl, ctrl_reg_6_xl, ctrl_reg_7_xl, ctrl_reg_10] =
+            values;
+
+        let (accel_gyro_sampling_rate, gyro_full_scale) =
+            registers::decode_ctrl_reg_1_g(ctrl_reg_1_g)?;
+        let gyro_low_pass_filter = registers::decode_ctrl_reg_2_g(ctrl_reg_2_g);
+        let (gyro_low_power_mode, gyro_high_pass_filter) =
+            registers::decode_ctrl_reg_3_g(ctrl_reg_3_g);
+        let (
+            gyro_x_axis_enabled,
+            gyro_y_axis_enabled,
+            gyro_z_axis_enabled,
+            accel_interrupt_latched,
+        ) = registers::decode_ctrl_reg_4(ctrl_reg_4);
+        let (accel_decimation, accel_x_axis_enabled, accel_y_axis_enabled, accel_z_axis_enabled) =
+            registers::decode_ctrl_reg_5_xl(ctrl_reg_5_xl);
+        let (accel_only_sampling_rate, accel_full_scale, accel_bandwidth) =
+            registers::decode_ctrl_reg_6_xl(ctrl_reg_6_xl)?;
+        let (accel_high_resolution, accel_low_pass_cutoff, accel_filter_enabled) =
+            registers::decode_ctrl_reg_7_xl(ctrl_reg_7_xl);
+        let (gyro_self_test, accel_self_test) = registers::decode_ctrl_reg_10(ctrl_reg_10);
+
+        Some(Self {
+            accel_gyro_sampling_rate,
+            accel_only_sampling_rate,
+            gyro_full_scale,
+            gyro_high_pass_filter,
+            gyro_low_pass_filter,
+            gyro_low_power_mode,
+            gyro_x_axis_enabled,
+            gyro_y_axis_enabled,
+            gyro_z_axis_enabled,
+            accel_interrupt_latched,
+            accel_decimation,
+            accel_x_axis_enabled,
+            accel_y_axis_enabled,
+            accel_z_axis_enabled,
+            accel_full_scale,
+            accel_bandwidth,
+            accel_high_resolution,
+            accel_low_pass_cutoff,
+            accel_filter_enabled,
+            gyro_self_test,
+            accel_self_test,
+            ..Default::default()
+        })
+    }
+
+    /// Record the names of all fields that differ from `other`.
+    pub(crate) fn differences(&self, other: &Self, differences: &mut ConfigDifferences) {
+        push_differences!(
+            differences,
+            "accel_gyro",
+            self,
+            other,
+            [
+                accel_gyro_sampling_rate,
+                accel_only_sampling_rate,
+                gyro_full_scale,
+                gyro_high_pass_filter,
+                gyro_low_pass_filter,
+                gyro_low_power_mode,
+                gyro_x_axis_enabled,
+                gyro_y_axis_enabled,
+                gyro_z_axis_enabled,
+                accel_interrupt_latched,
+                accel_decimation,
+                accel_x_axis_enabled,
+                accel_y_axis_enabled,
+                accel_z_axis_enabled,
+                accel_full_scale,
+                accel_bandwidth,
+                accel_high_resolution,
+                accel_low_pass_cutoff,
+                accel_filter_enabled,
+                gyro_self_test,
+                accel_self_test,
+                endianness
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+use crate::measurements::{Acceleration, AngularRate, RawSample};
+
+#[test]
+fn accel_datasheet_scaling() {
+    let raw = RawSample {
+        x: 16384,
+        y: -16384,
+        z: 1,
+    };
+
+    for (scale, expected) in [
+        (AccelFullScale::PlusMinus2g, 0.999424),
+        (AccelFullScale::PlusMinus4g, 1.998848),
+        (AccelFullScale::PlutMinus8g, 3.997696),
+        (AccelFullScale::PlusMinus16g, 11.993088),
+    ] {
+        let acceleration = Acceleration::from_raw(raw, scale.sensitivity());
+        assert!((acceleration.x - expected).abs() < 1e-4, "Wrong X value");
+        assert!((acceleration.y + expected).abs() < 1e-4, "Wrong Y value");
+        assert!(
+            (acceleration.z - scale.sensitivity()).abs() < 1e-9,
+            "Wrong Z value"
+        );
+    }
+}
+
+#[test]
+fn gyro_datasheet_scaling() {
+    let raw = RawSample {
+        x: 1000,
+        y: -1000,
+        z: i16::MAX,
+    };
+
+    for (scale, expected) in [
+        (GyroFullScale::_245dps, 8.75),
+        (GyroFullScale::_500dps, 17.5),
+        (GyroFullScale::_2000dps, 70.0),
+    ] {
+        let rate = AngularRate::from_raw(raw, scale.sensitivity());
+        assert!((rate.x - expected).abs() < 1e-3, "Wrong X value");
+        assert!((rate.y + expected).abs() < 1e-3, "Wrong Y value");
+        assert!(rate.z >= f32::from(scale), "Full scale not reachable");
+    }
 }
@@ -0,0 +1,79 @@
+use super::accel_gyro::AccelFullScale;
+use crate::registers::{self, Register};
+
+/// Gyroscope state while the device is inactive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GyroInactivityMode {
+    /// Gyroscope is powered down.
+    PowerDown,
+    /// Gyroscope is put to sleep, which allows a faster wake-up.
+    Sleep,
+}
+
+/// Inactivity detection configuration. The device is considered inactive once the acceleration
+/// stays below the threshold for the configured duration, and the accelerometer and gyroscope
+/// sampling rates are reduced until activity is detected again. The inactivity event can be routed
+/// to INT2_A/G through `Int2Routing::inactivity`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InactivityConfig {
+    /// Inactivity threshold in g. A threshold of zero disables inactivity detection. Values above
+    /// the maximum threshold are clamped.
+    pub threshold: f32,
+    /// Number of samples the acceleration has to stay below the threshold.
+    pub duration: u8,
+    /// Gyroscope state while the device is inactive.
+    pub gyro_mode: GyroInactivityMode,
+}
+
+impl Default for InactivityConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.0,
+            duration: 0,
+            gyro_mode: GyroInactivityMode::PowerDown,
+        }
+    }
+}
+
+impl InactivityConfig {
+    pub fn all_registers(&self, full_scale: AccelFullScale) -> [(Register, u8); 2] {
+        let act_ths = registers::act_ths(self.threshold, full_scale.sensitivity(), self.gyro_mode);
+
+        [
+            (Register::ACT_THS, act_ths),
+            (Register::ACT_DUR, self.duration),
+        ]
+    }
+}
+
+/// Activity state of the device.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ActivityState {
+    /// The device is active.
+    Active,
+    /// The device is inactive.
+    Inactive,
+}
+
+#[test]
+fn inactivity_registers() {
+    let config = InactivityConfig {
+        threshold: 0.25,
+        duration: 50,
+        gyro_mode: GyroInactivityMode::Sleep,
+    };
+
+    let [(_, act_ths), (_, act_dur)] = config.all_registers(AccelFullScale::PlusMinus2g);
+
+    assert_eq!(act_ths, 0b1001_0000, "Wrong ACT_THS value");
+    assert_eq!(act_dur, 50, "Wrong ACT_DUR value");
+
+    let config = InactivityConfig {
+        threshold: 10.0,
+        ..Default::default()
+    };
+
+    let [(_, act_ths), _] = config.all_registers(AccelFullScale::PlusMinus2g);
+
+    assert_eq!(act_ths, 0b0111_1111, "Threshold not clamped");
+}
@@ -0,0 +1,135 @@
+use super::ConfigDifferences;
+use crate::registers::{self, Register};
+
+/// Maximum number of samples the FIFO can hold.
+pub const FIFO_DEPTH: u8 = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FifoMode {
+    /// FIFO is turned off and its content is cleared.
+    Bypass,
+    /// Samples are collected until the FIFO is full, then collection stops.
+    Fifo,
+    /// Samples are collected continuously, the oldest ones are overwritten when the FIFO is full.
+    Continuous,
+    /// Continuous mode until an interrupt event occurs, then FIFO mode.
+    ContinuousToFifo,
+    /// Bypass mode until an interrupt event occurs, then continuous mode.
+    BypassToContinuous,
+}
+
+/// FIFO configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FifoConfig {
+    /// Enable the FIFO memory.
+    pub enabled: bool,
+    /// FIFO mode.
+    pub mode: FifoMode,
+    /// FIFO threshold level. Values above 31 are clamped.
+    pub threshold: u8,
+    /// Limit the FIFO depth to the threshold level.
+    pub stop_on_threshold: bool,
+}
+
+impl Default for FifoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: FifoMode::Bypass,
+            threshold: 0,
+            stop_on_threshold: false,
+        }
+    }
+}
+
+impl FifoConfig {
+    pub fn all_registers(&self) -> [(Register, u8); 2] {
+        let ctrl_reg_9 = registers::ctrl_reg_9(self.enabled, self.stop_on_threshold);
+        let fifo_ctrl = registers::fifo_ctrl(self.mode, self.threshold);
+
+        [
+            (Register::CTRL_REG9, ctrl_reg_9),
+            (Register::FIFO_CTRL, fifo_ctrl),
+        ]
+    }
+
+    /// Rebuild the configuration from register values, in the order of `all_registers()`.
+    /// Returns `None` if FIFO_CTRL holds a reserved mode.
+    pub(crate) fn from_registers(values: [u8; 2]) -> Option<Self> {
+        let [ctrl_reg_9, fifo_ctrl] = values;
+
+        let (enabled, stop_on_threshold) = registers::decode_ctrl_reg_9(ctrl_reg_9);
+        let (mode, threshold) = registers::decode_fifo_ctrl(fifo_ctrl)?;
+
+        Some(Self {
+            enabled,
+            mode,
+            threshold,
+            stop_on_threshold,
+        })
+    }
+
+    /// Record the names of all fields that differ from `other`.
+    pub(crate) fn differences(&self, other: &Self, differences: &mut ConfigDifferences) {
+        push_differences!(
+            differences,
+            "fifo",
+            self,
+            other,
+            [enabled, mode, stop_on_threshold]
+        );
+
+        // The threshold is clamped when written.
+        if self.threshold.min(0b1_1111) != other.threshold.min(0b1_1111) {
+            let _ = differences.push("fifo.threshold");
+        }
+    }
+}
+
+/// FIFO status, as reported by the `FIFO_SRC` register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FifoStatus {
+    /// Number of unread samples stored in the FIFO.
+    pub level: u8,
+    /// The FIFO is full and at least one sample has been overwritten.
+    pub overrun: bool,
+    /// The FIFO level is equal to or higher than the threshold level.
+    pub threshold_reached: bool,
+}
+
+#[test]
+fn fifo_registers() {
+    let config = FifoConfig {
+        enabled: true,
+        mode: FifoMode::Continuous,
+        threshold: 40,
+        stop_on_threshold: true,
+    };
+
+    let [(_, ctrl_reg_9), (_, fifo_ctrl)] = config.all_registers();
+
+    assert_eq!(ctrl_reg_9, 0b0000_0011, "Wrong CTRL_REG9 value");
+    assert_eq!(fifo_ctrl, 0b1101_1111, "Wrong FIFO_CTRL value");
+}
+
+#[test]
+fn fifo_status() {
+    assert_eq!(
+        registers::fifo_src(0b1010_0000),
+        FifoStatus {
+            level: 32,
+            overrun: false,
+            threshold_reached: true,
+        },
+        "Wrong decoded FIFO_SRC value"
+    );
+    assert_eq!(
+        registers::fifo_src(0b0100_0101),
+        FifoStatus {
+            level: 5,
+            overrun: true,
+            threshold_reached: false,
+        },
+        "Wrong decoded FIFO_SRC value"
+    );
+}
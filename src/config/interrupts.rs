@@ -0,0 +1,457 @@
+use super::accel_gyro::{AccelFullScale, GyroFullScale};
+use super::magnetometer::FullScale;
+use super::ConfigDifferences;
+use crate::registers::{self, Register};
+
+/// Signals routed to the INT1_A/G pin.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Int1Routing {
+    /// Gyroscope interrupt generator event.
+    pub gyro_interrupt: bool,
+    /// Accelerometer interrupt generator event.
+    pub accel_interrupt: bool,
+    /// FIFO is full.
+    pub fifo_full: bool,
+    /// FIFO overrun.
+    pub fifo_overrun: bool,
+    /// FIFO threshold reached.
+    pub fifo_threshold: bool,
+    /// Boot in progress.
+    pub boot_status: bool,
+    /// New gyroscope data available.
+    pub gyro_data_ready: bool,
+    /// New accelerometer data available.
+    pub accel_data_ready: bool,
+}
+
+/// Signals routed to the INT2_A/G pin.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Int2Routing {
+    /// Inactivity detected.
+    pub inactivity: bool,
+    /// FIFO is full.
+    pub fifo_full: bool,
+    /// FIFO overrun.
+    pub fifo_overrun: bool,
+    /// FIFO threshold reached.
+    pub fifo_threshold: bool,
+    /// New temperature data available.
+    pub temperature_data_ready: bool,
+    /// New gyroscope data available.
+    pub gyro_data_ready: bool,
+    /// New accelerometer data available.
+    pub accel_data_ready: bool,
+}
+
+/// Routing of interrupt signals to the INT1_A/G and INT2_A/G pins. Polarity and output stage of
+/// both pins are set by `DeviceConfig::interrupt_active_low` and `DeviceConfig::interrupt_pin_mode`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterruptRouting {
+    /// Signals routed to INT1_A/G.
+    pub int1: Int1Routing,
+    /// Signals routed to INT2_A/G.
+    pub int2: Int2Routing,
+}
+
+impl InterruptRouting {
+    pub fn all_registers(&self) -> [(Register, u8); 2] {
+        [
+            (Register::INT1_CTRL, registers::int1_ctrl(self.int1)),
+            (Register::INT2_CTRL, registers::int2_ctrl(self.int2)),
+        ]
+    }
+
+    /// Rebuild the routing from register values, in the order of `all_registers()`.
+    pub(crate) fn from_registers(values: [u8; 2]) -> Self {
+        let [int1_ctrl, int2_ctrl] = values;
+
+        Self {
+            int1: registers::decode_int1_ctrl(int1_ctrl),
+            int2: registers::decode_int2_ctrl(int2_ctrl),
+        }
+    }
+
+    /// Record the names of all fields that differ from `other`.
+    pub(crate) fn differences(&self, other: &Self, differences: &mut ConfigDifferences) {
+        push_differences!(differences, "interrupt_routing", self, other, [int1, int2]);
+    }
+}
+
+/// How enabled axis events are combined into an interrupt.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EventCombination {
+    /// Interrupt if any of the enabled events occurs.
+    Or,
+    /// Interrupt if all of the enabled events occur.
+    And,
+}
+
+/// Per-axis high and low events.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AxisEvents {
+    /// X-axis value above threshold.
+    pub x_high: bool,
+    /// X-axis value below threshold.
+    pub x_low: bool,
+    /// Y-axis value above threshold.
+    pub y_high: bool,
+    /// Y-axis value below threshold.
+    pub y_low: bool,
+    /// Z-axis value above threshold.
+    pub z_high: bool,
+    /// Z-axis value below threshold.
+    pub z_low: bool,
+}
+
+/// Interrupt generator state, as reported by the interrupt source registers.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterruptSource {
+    /// One or more interrupt events have been generated.
+    pub active: bool,
+    /// Events that occurred.
+    pub events: AxisEvents,
+}
+
+/// Accelerometer interrupt generator configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccelInterruptConfig {
+    /// Events that generate an interrupt.
+    pub events: AxisEvents,
+    /// How enabled events are combined.
+    pub combination: EventCombination,
+    /// X-axis threshold in g.
+    pub threshold_x: f32,
+    /// Y-axis threshold in g.
+    pub threshold_y: f32,
+    /// Z-axis threshold in g.
+    pub threshold_z: f32,
+    /// Wait for `duration` samples before exiting the interrupt.
+    pub wait: bool,
+    /// Number of samples an event has to persist to generate an interrupt. Values above 127 are
+    /// clamped.
+    pub duration: u8,
+}
+
+impl Default for AccelInterruptConfig {
+    fn default() -> Self {
+        Self {
+            events: Default::default(),
+            combination: EventCombination::Or,
+            threshold_x: 0.0,
+            threshold_y: 0.0,
+            threshold_z: 0.0,
+            wait: false,
+            duration: 0,
+        }
+    }
+}
+
+impl AccelInterruptConfig {
+    pub fn all_registers(&self, full_scale: AccelFullScale) -> [(Register, u8); 5] {
+        let sensitivity = full_scale.sensitivity();
+
+        [
+            (
+                Register::INT_GEN_CFG_XL,
+                registers::int_gen_cfg_xl(self.combination, self.events),
+            ),
+            (
+                Register::INT_GEN_THS_X_XL,
+                registers::int_gen_ths_xl(self.threshold_x, sensitivity),
+            ),
+            (
+                Register::INT_GEN_THS_Y_XL,
+                registers::int_gen_ths_xl(self.threshold_y, sensitivity),
+            ),
+            (
+                Register::INT_GEN_THS_Z_XL,
+                registers::int_gen_ths_xl(self.threshold_z, sensitivity),
+            ),
+            (
+                Register::INT_GEN_DUR_XL,
+                registers::int_gen_dur(self.wait, self.duration),
+            ),
+        ]
+    }
+}
+
+/// Behavior of the gyroscope interrupt duration counter when an event is no longer present.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CounterMode {
+    /// The counter is reset.
+    Reset,
+    /// The counter is decremented.
+    Decrement,
+}
+
+/// Gyroscope interrupt generator configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GyroInterruptConfig {
+    /// Events that generate an interrupt.
+    pub events: AxisEvents,
+    /// How enabled events are combined.
+    pub combination: EventCombination,
+    /// Latch interrupt requests until the source register is read.
+    pub latched: bool,
+    /// Behavior of the duration counter.
+    pub counter_mode: CounterMode,
+    /// X-axis threshold in dps.
+    pub threshold_x: f32,
+    /// Y-axis threshold in dps.
+    pub threshold_y: f32,
+    /// Z-axis threshold in dps.
+    pub threshold_z: f32,
+    /// Wait for `duration` samples before exiting the interrupt.
+    pub wait: bool,
+    /// Number of samples an event has to persist to generate an interrupt. Values above 127 are
+    /// clamped.
+    pub duration: u8,
+}
+
+impl Default for GyroInterruptConfig {
+    fn default() -> Self {
+        Self {
+            events: Default::default(),
+            combination: EventCombination::Or,
+            latched: false,
+            counter_mode: CounterMode::Reset,
+            threshold_x: 0.0,
+            threshold_y: 0.0,
+            threshold_z: 0.0,
+            wait: false,
+            duration: 0,
+        }
+    }
+}
+
+impl GyroInterruptConfig {
+    pub fn all_registers(&self, full_scale: GyroFullScale) -> [(Register, u8); 8] {
+        let sensitivity = full_scale.sensitivity();
+        let (ths_xh, ths_xl) = registers::int_gen_ths_g(self.threshold_x, sensitivity);
+        let (ths_yh, ths_yl) = registers::int_gen_ths_g(self.threshold_y, sensitivity);
+        let (ths_zh, ths_zl) = registers::int_gen_ths_g(self.threshold_z, sensitivity);
+
+        [
+            (
+                Register::INT_GEN_CFG_G,
+                registers::int_gen_cfg_g(self.combination, self.latched, self.events),
+            ),
+            (
+                Register::INT_GEN_THS_XH_G,
+                registers::int_gen_ths_xh_g(self.counter_mode, ths_xh),
+            ),
+            (Register::INT_GEN_THS_XL_G, ths_xl),
+            (Register::INT_GEN_THS_YH_G, ths_yh),
+            (Register::INT_GEN_THS_YL_G, ths_yl),
+            (Register::INT_GEN_THS_ZH_G, ths_zh),
+            (Register::INT_GEN_THS_ZL_G, ths_zl),
+            (
+                Register::INT_GEN_DUR_G,
+                registers::int_gen_dur(self.wait, self.duration),
+            ),
+        ]
+    }
+}
+
+/// Magnetometer interrupt configuration. The interrupt is signaled on the INT_M pin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MagInterruptConfig {
+    /// Enable the interrupt.
+    pub enabled: bool,
+    /// Enable interrupt generation on the X-axis.
+    pub x_axis_enabled: bool,
+    /// Enable interrupt generation on the Y-axis.
+    pub y_axis_enabled: bool,
+    /// Enable interrupt generation on the Z-axis.
+    pub z_axis_enabled: bool,
+    /// Drive INT_M high when the interrupt is active, instead of low.
+    pub active_high: bool,
+    /// Latch interrupt requests until the source register is read.
+    pub latched: bool,
+    /// Threshold in gauss, compared against the absolute value of each enabled axis.
+    pub threshold: f32,
+}
+
+impl Default for MagInterruptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            x_axis_enabled: false,
+            y_axis_enabled: false,
+            z_axis_enabled: false,
+            active_high: false,
+            latched: true,
+            threshold: 0.0,
+        }
+    }
+}
+
+impl MagInterruptConfig {
+    pub fn all_registers(&self, full_scale: FullScale) -> [(Register, u8); 3] {
+        let int_cfg_m = registers::int_cfg_m(
+            self.x_axis_enabled,
+            self.y_axis_enabled,
+            self.z_axis_enabled,
+            self.active_high,
+            self.latched,
+            self.enabled,
+        );
+        let (ths_l, ths_h) = registers::int_ths_m(self.threshold, full_scale.sensitivity());
+
+        [
+            (Register::INT_CFG_M, int_cfg_m),
+            (Register::INT_THS_L_M, ths_l),
+            (Register::INT_THS_H_M, ths_h),
+        ]
+    }
+}
+
+/// Magnetometer interrupt state, as reported by the `INT_SRC_M` register.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MagInterruptSource {
+    /// X-axis value exceeds the threshold on the positive side.
+    pub positive_x: bool,
+    /// Y-axis value exceeds the threshold on the positive side.
+    pub positive_y: bool,
+    /// Z-axis value exceeds the threshold on the positive side.
+    pub positive_z: bool,
+    /// X-axis value exceeds the threshold on the negative side.
+    pub negative_x: bool,
+    /// Y-axis value exceeds the threshold on the negative side.
+    pub negative_y: bool,
+    /// Z-axis value exceeds the threshold on the negative side.
+    pub negative_z: bool,
+    /// Internal measurement range overflowed.
+    pub overflow: bool,
+    /// An interrupt event occurred.
+    pub active: bool,
+}
+
+#[test]
+fn interrupt_routing_registers() {
+    let routing = InterruptRouting {
+        int1: Int1Routing {
+            accel_interrupt: true,
+            fifo_threshold: true,
+            accel_data_ready: true,
+            ..Default::default()
+        },
+        int2: Int2Routing {
+            inactivity: true,
+            temperature_data_ready: true,
+            ..Default::default()
+        },
+    };
+
+    let [(_, int1_ctrl), (_, int2_ctrl)] = routing.all_registers();
+
+    assert_eq!(int1_ctrl, 0b0100_1001, "Wrong INT1_CTRL value");
+    assert_eq!(int2_ctrl, 0b1000_0100, "Wrong INT2_CTRL value");
+}
+
+#[test]
+fn accel_interrupt_registers() {
+    let config = AccelInterruptConfig {
+        events: AxisEvents {
+            x_high: true,
+            z_low: true,
+            ..Default::default()
+        },
+        combination: EventCombination::And,
+        threshold_x: 0.5,
+        threshold_y: 5.0,
+        threshold_z: 0.25,
+        wait: true,
+        duration: 10,
+    };
+
+    let registers = config.all_registers(AccelFullScale::PlusMinus2g);
+    let values = registers.map(|(_, value)| value);
+
+    assert_eq!(values[0], 0b1001_0010, "Wrong INT_GEN_CFG_XL value");
+    assert_eq!(values[1], 32, "Wrong X threshold");
+    assert_eq!(values[2], 0xff, "Y threshold not clamped");
+    assert_eq!(values[3], 16, "Wrong Z threshold");
+    assert_eq!(values[4], 0b1000_1010, "Wrong INT_GEN_DUR_XL value");
+}
+
+#[test]
+fn gyro_interrupt_registers() {
+    let config = GyroInterruptConfig {
+        events: AxisEvents {
+            y_high: true,
+            y_low: true,
+            ..Default::default()
+        },
+        combination: EventCombination::Or,
+        latched: true,
+        counter_mode: CounterMode::Decrement,
+        threshold_x: 70.0,
+        threshold_y: 1000.0,
+        threshold_z: 0.0,
+        wait: false,
+        duration: 200,
+    };
+
+    let registers = config.all_registers(GyroFullScale::_245dps);
+    let values = registers.map(|(_, value)| value);
+
+    assert_eq!(values[0], 0b0100_1100, "Wrong INT_GEN_CFG_G value");
+    assert_eq!([values[1], values[2]], [0x9f, 0x40], "Wrong X threshold");
+    assert_eq!(
+        [values[3], values[4]],
+        [0x7f, 0xff],
+        "Y threshold not clamped"
+    );
+    assert_eq!([values[5], values[6]], [0x00, 0x00], "Wrong Z threshold");
+    assert_eq!(values[7], 0b0111_1111, "Duration not clamped");
+}
+
+#[test]
+fn interrupt_source() {
+    assert_eq!(
+        registers::int_gen_src(0b0110_0001),
+        InterruptSource {
+            active: true,
+            events: AxisEvents {
+                z_high: true,
+                x_low: true,
+                ..Default::default()
+            },
+        },
+        "Wrong decoded interrupt source"
+    );
+}
+
+#[test]
+fn mag_interrupt_registers() {
+    let config = MagInterruptConfig {
+        enabled: true,
+        x_axis_enabled: true,
+        z_axis_enabled: true,
+        active_high: true,
+        threshold: 0.7,
+        ..Default::default()
+    };
+
+    let registers = config.all_registers(FullScale::PlusMinus4Gauss);
+    let values = registers.map(|(_, value)| value);
+
+    assert_eq!(values[0], 0b1010_1101, "Wrong INT_CFG_M value");
+    assert_eq!([values[1], values[2]], [0x88, 0x13], "Wrong threshold");
+}
+
+#[test]
+fn mag_interrupt_source() {
+    assert_eq!(
+        registers::int_src_m(0b1000_0111),
+        MagInterruptSource {
+            positive_x: true,
+            negative_z: true,
+            overflow: true,
+            active: true,
+            ..Default::default()
+        },
+        "Wrong decoded INT_SRC_M value"
+    );
+}
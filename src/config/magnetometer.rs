@@ -1,7 +1,8 @@
-use super::Endianness;
+use super::{ConfigDifferences, Endianness, SPIMode};
+use crate::measurements::MagneticField;
 use crate::registers::{self, Register};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PerformanceMode {
     LowPower,
     MediumPerformance,
@@ -9,7 +10,7 @@ pub enum PerformanceMode {
     UtraHighPerformance,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SamplingRate {
     _0p625Hz,
     _1p25Hz,
@@ -21,7 +22,7 @@ pub enum SamplingRate {
     _80Hz,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FullScale {
     PlusMinus4Gauss,
     PlusMinus8Gauss,
@@ -40,7 +41,19 @@ impl From<FullScale> for f32 {
     }
 }
 
-#[derive(Copy, Clone)]
+impl FullScale {
+    /// Sensitivity in gauss/LSB, as specified in the datasheet.
+    pub fn sensitivity(self) -> f32 {
+        match self {
+            Self::PlusMinus4Gauss => 0.00014,
+            Self::PlusMinus8Gauss => 0.00029,
+            Self::PlusMinus12Gauss => 0.00043,
+            Self::PlusMinus16Gauss => 0.00058,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum OperatingMode {
     ContinuousConversion,
     SingleConversion,
@@ -48,6 +61,7 @@ pub enum OperatingMode {
 }
 
 /// Magnetometer configuration.
+#[derive(Clone, Debug, PartialEq)]
 pub struct MagnetometerConfig {
     /// Enable internal temperature compensation.
     pub temperature_compensation: bool,
@@ -68,10 +82,13 @@ pub struct MagnetometerConfig {
     /// Low power mode. If this is set to true, sampling rate is set to 0.625Hz and the system
     /// performs, for each channel, the minimum number of averages.
     pub low_power_mode: bool,
-    /// Set SPI interface to write only.
+    /// Set SPI interface to write only. Ignored in 3-wire SPI mode, which always needs reads and
+    /// writes on the shared data line.
     pub spi_write_only: bool,
     /// Operating mode.
     pub operating_mode: OperatingMode,
+    /// Hard-iron offset in gauss, subtracted from the output by the device.
+    pub hard_iron_offset: MagneticField,
     /// Endianness of data returned in data registers.
     data_endianness: Endianness,
     /// Allows reading only high parts of data registers to increase reading efficiency.
@@ -94,6 +111,7 @@ impl Default for MagnetometerConfig {
             low_power_mode: false,
             spi_write_only: true,
             operating_mode: OperatingMode::PowerDown,
+            hard_iron_offset: MagneticField::default(),
             data_endianness: Endianness::BigEndian,
             fast_read: false,
             block_data_update: false,
@@ -102,7 +120,12 @@ impl Default for MagnetometerConfig {
 }
 
 impl MagnetometerConfig {
-    pub fn all_registers(&self) -> [(Register, u8); 5] {
+    /// Whether the SPI interface is write only, given the SPI mode of the device.
+    pub(crate) fn effective_spi_write_only(&self, spi_mode: SPIMode) -> bool {
+        self.spi_write_only && matches!(spi_mode, SPIMode::FourWire)
+    }
+
+    pub fn all_registers(&self, spi_mode: SPIMode) -> [(Register, u8); 11] {
         let ctrl_reg_1 = registers::ctrl_reg_1_m(
             self.temperature_compensation,
             self.xy_performance_mode,
@@ -114,11 +137,12 @@ impl MagnetometerConfig {
         let ctrl_reg_3 = registers::ctrl_rg_3_m(
             self.i2c_disabled,
             self.low_power_mode,
-            self.spi_write_only,
+            self.effective_spi_write_only(spi_mode),
             self.operating_mode,
         );
         let ctrl_reg_4 = registers::ctrl_reg_4_m(self.z_performance_mode, self.data_endianness);
         let ctrl_reg_5 = registers::ctrl_reg_5_m(self.fast_read, self.block_data_update);
+        let [offset_x, offset_y, offset_z] = self.offset_registers();
 
         [
             (Register::CTRL_REG1_M, ctrl_reg_1),
@@ -126,6 +150,160 @@ impl MagnetometerConfig {
             (Register::CTRL_REG3_M, ctrl_reg_3),
             (Register::CTRL_REG4_M, ctrl_reg_4),
             (Register::CTRL_REG5_M, ctrl_reg_5),
+            (Register::OFFSET_X_REG_L_M, offset_x.0),
+            (Register::OFFSET_X_REG_H_M, offset_x.1),
+            (Register::OFFSET_Y_REG_L_M, offset_y.0),
+            (Register::OFFSET_Y_REG_H_M, offset_y.1),
+            (Register::OFFSET_Z_REG_L_M, offset_z.0),
+            (Register::OFFSET_Z_REG_H_M, offset_z.1),
         ]
     }
+
+    /// Hard-iron offset register values as low and high byte for the X, Y and Z axis, converted
+    /// with the current full scale.
+    pub(crate) fn offset_registers(&self) -> [(u8, u8); 3] {
+        let sensitivity = self.full_scale.sensitivity();
+
+        [
+            registers::offset_m(self.hard_iron_offset.x, sensitivity),
+            registers::offset_m(self.hard_iron_offset.y, sensitivity),
+            registers::offset_m(self.hard_iron_offset.z, sensitivity),
+        ]
+    }
+
+    /// Rebuild the configuration from register values, in the order of `all_registers()`.
+    pub(crate) fn from_registers(values: [u8; 11]) -> Self {
+        let [ctrl_reg_1, ctrl_reg_2, ctrl_reg_3, ctrl_reg_4, ctrl_reg_5, offset @ ..] = values;
+
+        let (
+            temperature_compensation,
+            xy_performance_mode,
+            sampling_rate,
+            fast_sampling,
+            self_test,
+        ) = registers::decode_ctrl_reg_1_m(ctrl_reg_1);
+        let full_scale = registers::decode_ctrl_reg_2_m(ctrl_reg_2);
+        let (i2c_disabled, low_power_mode, spi_write_only, operating_mode) =
+            registers::decode_ctrl_reg_3_m(ctrl_reg_3);
+        let (z_performance_mode, data_endianness) = registers::decode_ctrl_reg_4_m(ctrl_reg_4);
+        let (fast_read, block_data_update) = registers::decode_ctrl_reg_5_m(ctrl_reg_5);
+        let sensitivity = full_scale.sensitivity();
+        let hard_iron_offset = MagneticField {
+            x: registers::decode_offset_m(offset[0], offset[1], sensitivity),
+            y: registers::decode_offset_m(offset[2], offset[3], sensitivity),
+            z: registers::decode_offset_m(offset[4], offset[5], sensitivity),
+        };
+
+        Self {
+            temperature_compensation,
+            xy_performance_mode,
+            z_performance_mode,
+            sampling_rate,
+            fast_sampling,
+            self_test,
+            full_scale,
+            i2c_disabled,
+            low_power_mode,
+            spi_write_only,
+            operating_mode,
+            hard_iron_offset,
+            data_endianness,
+            fast_read,
+            block_data_update,
+        }
+    }
+
+    /// Record the names of all fields that differ from `other`. The SPI write only flag is
+    /// compared as applied in the given SPI mode.
+    pub(crate) fn differences(
+        &self,
+        other: &Self,
+        spi_mode: SPIMode,
+        differences: &mut ConfigDifferences,
+    ) {
+        push_differences!(
+            differences,
+            "magnetometer",
+            self,
+            other,
+            [
+                temperature_compensation,
+                xy_performance_mode,
+                z_performance_mode,
+                sampling_rate,
+                fast_sampling,
+                self_test,
+                full_scale,
+                i2c_disabled,
+                low_power_mode,
+                operating_mode,
+                data_endianness,
+                fast_read,
+                block_data_update
+            ]
+        );
+
+        if self.effective_spi_write_only(spi_mode) != other.effective_spi_write_only(spi_mode) {
+            let _ = differences.push("magnetometer.spi_write_only");
+        }
+
+        // Offsets are compared as written, since the conversion to register values is lossy.
+        if self.offset_registers() != other.offset_registers() {
+            let _ = differences.push("magnetometer.hard_iron_offset");
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::measurements::RawSample;
+
+#[test]
+fn datasheet_scaling() {
+    let raw = RawSample {
+        x: 10000,
+        y: -10000,
+        z: 0,
+    };
+
+    for (scale, expected) in [
+        (FullScale::PlusMinus4Gauss, 1.4),
+        (FullScale::PlusMinus8Gauss, 2.9),
+        (FullScale::PlusMinus12Gauss, 4.3),
+        (FullScale::PlusMinus16Gauss, 5.8),
+    ] {
+        let field = MagneticField::from_raw(raw, scale.sensitivity());
+        assert!((field.x - expected).abs() < 1e-4, "Wrong X value");
+        assert!((field.y + expected).abs() < 1e-4, "Wrong Y value");
+        assert_eq!(field.z, 0.0, "Wrong Z value");
+    }
+}
+
+#[test]
+fn hard_iron_offset_registers() {
+    let config = MagnetometerConfig {
+        full_scale: FullScale::PlusMinus8Gauss,
+        hard_iron_offset: MagneticField {
+            x: 0.29,
+            y: -0.58,
+            z: 100.0,
+        },
+        ..Default::default()
+    };
+
+    let registers = config.all_registers(SPIMode::FourWire);
+    let offsets: [u8; 6] = core::array::from_fn(|i| registers[5 + i].1);
+    assert_eq!(
+        offsets,
+        [0xe8, 0x03, 0x30, 0xf8, 0xff, 0x7f],
+        "Wrong offset registers"
+    );
+
+    let decoded = MagnetometerConfig::from_registers(registers.map(|(_, value)| value));
+    assert!(
+        (decoded.hard_iron_offset.y + 0.58).abs() < 1e-3,
+        "Wrong decoded offset"
+    );
+    let mut differences = ConfigDifferences::new();
+    config.differences(&decoded, SPIMode::FourWire, &mut differences);
+    assert!(differences.is_empty(), "Offsets differ: {differences:?}");
 }
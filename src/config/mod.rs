@@ -2,31 +2,61 @@ use crate::registers::{self, Register};
 
 use heapless::Vec;
 
+/// Push the path of each listed field that differs between two configurations.
+macro_rules! push_differences {
+    ($differences:expr, $a:expr, $b:expr, [$($field:ident),*]) => {
+        $(
+            if $a.$field != $b.$field {
+                let _ = $differences.push(stringify!($field));
+            }
+        )*
+    };
+    ($differences:expr, $prefix:literal, $a:expr, $b:expr, [$($field:ident),*]) => {
+        $(
+            if $a.$field != $b.$field {
+                let _ = $differences.push(concat!($prefix, ".", stringify!($field)));
+            }
+        )*
+    };
+}
+
 pub mod accel_gyro;
+pub mod activity;
+pub mod fifo;
+pub mod interrupts;
 pub mod magnetometer;
 
 pub use accel_gyro::AccelGyroConfig;
+pub use activity::InactivityConfig;
+pub use fifo::FifoConfig;
+pub use interrupts::{
+    AccelInterruptConfig, GyroInterruptConfig, InterruptRouting, MagInterruptConfig,
+};
 pub use magnetometer::MagnetometerConfig;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InterruptPinMode {
     PushPull,
     OpenDrain,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SPIMode {
     ThreeWire,
     FourWire,
 }
 
 #[allow(dead_code)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Endianness {
     BigEndian,
     LittleEndian,
 }
 
+/// Paths of configuration fields that differ, e.g. `"accel_gyro.gyro_full_scale"`.
+pub type ConfigDifferences = Vec<&'static str, 64>;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct DeviceConfig {
     reboot_memory_content: bool,
     block_data_update: bool,
@@ -36,6 +66,12 @@ pub struct DeviceConfig {
     register_increment: bool,
     pub magnetometer: MagnetometerConfig,
     pub accel_gyro: AccelGyroConfig,
+    pub fifo: FifoConfig,
+    pub interrupt_routing: InterruptRouting,
+    pub accel_interrupt: AccelInterruptConfig,
+    pub gyro_interrupt: GyroInterruptConfig,
+    pub magnetometer_interrupt: MagInterruptConfig,
+    pub inactivity: InactivityConfig,
 }
 
 /// Device configuration.
@@ -50,15 +86,40 @@ impl Default for DeviceConfig {
             register_increment: true,
             magnetometer: Default::default(),
             accel_gyro: Default::default(),
+            fifo: Default::default(),
+            interrupt_routing: Default::default(),
+            accel_interrupt: Default::default(),
+            gyro_interrupt: Default::default(),
+            magnetometer_interrupt: Default::default(),
+            inactivity: Default::default(),
         }
     }
 }
 
 impl DeviceConfig {
-    pub fn all_registers(&self) -> Vec<(Register, u8), 16> {
-        let mut all_registers = Vec::new();
+    /// Registers selecting the SPI mode of both components, with all other settings at their
+    /// power-on values. Used to switch to 3-wire mode before anything is read from the device.
+    pub(crate) fn spi_mode_registers(spi_mode: SPIMode) -> [(Register, u8); 2] {
+        let config = Self {
+            spi_mode,
+            ..Default::default()
+        };
+        let ctrl_reg_3_m = registers::ctrl_rg_3_m(
+            false,
+            false,
+            config.magnetometer.effective_spi_write_only(spi_mode),
+            magnetometer::OperatingMode::PowerDown,
+        );
+
+        [
+            (Register::CTRL_REG8, config.ctrl_reg_8()),
+            (Register::CTRL_REG3_M, ctrl_reg_3_m),
+        ]
+    }
 
-        let ctrl_register_8 = registers::ctrl_reg8(
+    /// Value of CTRL_REG8, without triggering a software reset.
+    pub(crate) fn ctrl_reg_8(&self) -> u8 {
+        registers::ctrl_reg8(
             self.reboot_memory_content,
             self.block_data_update,
             self.interrupt_active_low,
@@ -67,18 +128,237 @@ impl DeviceConfig {
             self.register_increment,
             self.accel_gyro.endianness,
             false,
-        );
+        )
+    }
+
+    pub fn all_registers(&self) -> Vec<(Register, u8), 48> {
+        let mut all_registers = Vec::new();
+
+        let ctrl_register_8 = self.ctrl_reg_8();
 
         all_registers
             .extend_from_slice(&[(Register::CTRL_REG8, ctrl_register_8)])
             .unwrap(); // +1 element -> 1 element total
 
         let ag_registers = self.accel_gyro.all_registers();
-        all_registers.extend_from_slice(&ag_registers).unwrap(); // +7 elements -> 8 elements total
+        all_registers.extend_from_slice(&ag_registers).unwrap(); // +8 elements -> 9 elements total
+
+        let mag_registers = self.magnetometer.all_registers(self.spi_mode);
+        all_registers.extend_from_slice(&mag_registers).unwrap(); // +11 elements -> 20 elements total
+
+        let fifo_registers = self.fifo.all_registers();
+        all_registers.extend_from_slice(&fifo_registers).unwrap(); // +2 elements -> 22 elements total
+
+        let interrupt_registers = self.interrupt_routing.all_registers();
+        all_registers
+            .extend_from_slice(&interrupt_registers)
+            .unwrap(); // +2 elements -> 24 elements total
 
-        let mag_registers = self.magnetometer.all_registers();
-        all_registers.extend_from_slice(&mag_registers).unwrap(); // +5 elements -> 13 elements total
+        let accel_interrupt_registers = self
+            .accel_interrupt
+            .all_registers(self.accel_gyro.accel_full_scale);
+        all_registers
+            .extend_from_slice(&accel_interrupt_registers)
+            .unwrap(); // +5 elements -> 29 elements total
+
+        let gyro_interrupt_registers = self
+            .gyro_interrupt
+            .all_registers(self.accel_gyro.gyro_full_scale);
+        all_registers
+            .extend_from_slice(&gyro_interrupt_registers)
+            .unwrap(); // +8 elements -> 37 elements total
 
+        let magnetometer_interrupt_registers = self
+            .magnetometer_interrupt
+            .all_registers(self.magnetometer.full_scale);
         all_registers
+            .extend_from_slice(&magnetometer_interrupt_registers)
+            .unwrap(); // +3 elements -> 40 elements total
+
+        let inactivity_registers = self
+            .inactivity
+            .all_registers(self.accel_gyro.accel_full_scale);
+        all_registers
+            .extend_from_slice(&inactivity_registers)
+            .unwrap(); // +2 elements -> 42 elements total
+
+        all_registers
+    }
+
+    /// Rebuild the configuration from the control register values read from a device, in the
+    /// order of `all_registers()`. Interrupt generator and inactivity settings can't be recovered
+    /// exactly from their registers and are copied from `self`. Returns `None` if a register holds
+    /// a reserved value.
+    pub(crate) fn with_registers(
+        &self,
+        ctrl_reg_8: u8,
+        accel_gyro: [u8; 8],
+        magnetometer: [u8; 11],
+        fifo: [u8; 2],
+        interrupt_routing: [u8; 2],
+    ) -> Option<Self> {
+        let (
+            reboot_memory_content,
+            block_data_update,
+            interrupt_active_low,
+            interrupt_pin_mode,
+            spi_mode,
+            register_increment,
+            endianness,
+        ) = registers::decode_ctrl_reg8(ctrl_reg_8);
+
+        let mut accel_gyro = AccelGyroConfig::from_registers(accel_gyro)?;
+        accel_gyro.endianness = endianness;
+
+        Some(Self {
+            reboot_memory_content,
+            block_data_update,
+            interrupt_active_low,
+            interrupt_pin_mode,
+            spi_mode,
+            register_increment,
+            magnetometer: MagnetometerConfig::from_registers(magnetometer),
+            accel_gyro,
+            fifo: FifoConfig::from_registers(fifo)?,
+            interrupt_routing: InterruptRouting::from_registers(interrupt_routing),
+            ..self.clone()
+        })
+    }
+
+    /// Paths of all fields decoded by `with_registers()` that differ from `other`. The
+    /// self-clearing reboot flag is not compared.
+    pub fn differences(&self, other: &Self) -> ConfigDifferences {
+        let mut differences = Vec::new();
+
+        push_differences!(
+            differences,
+            self,
+            other,
+            [
+                block_data_update,
+                interrupt_active_low,
+                interrupt_pin_mode,
+                spi_mode,
+                register_increment
+            ]
+        );
+        self.accel_gyro
+            .differences(&other.accel_gyro, &mut differences);
+        self.magnetometer
+            .differences(&other.magnetometer, self.spi_mode, &mut differences);
+        self.fifo.differences(&other.fifo, &mut differences);
+        self.interrupt_routing
+            .differences(&other.interrupt_routing, &mut differences);
+
+        differences
     }
 }
+
+#[test]
+fn three_wire_enables_magnetometer_spi_reads() {
+    let config = DeviceConfig {
+        spi_mode: SPIMode::ThreeWire,
+        ..Default::default()
+    };
+
+    let registers = config.all_registers();
+    let (_, ctrl_reg_8) = registers
+        .iter()
+        .find(|(reg, _)| matches!(reg, Register::CTRL_REG8))
+        .expect("CTRL_REG8 not written");
+    let (_, ctrl_reg_3_m) = registers
+        .iter()
+        .find(|(reg, _)| matches!(reg, Register::CTRL_REG3_M))
+        .expect("CTRL_REG3_M not written");
+
+    assert_eq!(ctrl_reg_8 & 0b1000, 0b1000, "AG not in 3-wire mode");
+    assert_eq!(ctrl_reg_3_m & 0b100, 0b100, "M not readable over SPI");
+}
+
+#[test]
+fn registers_round_trip() {
+    let mut config = DeviceConfig {
+        interrupt_active_low: true,
+        interrupt_pin_mode: InterruptPinMode::OpenDrain,
+        spi_mode: SPIMode::ThreeWire,
+        ..Default::default()
+    };
+    config.accel_gyro.gyro_full_scale = accel_gyro::GyroFullScale::_2000dps;
+    config.accel_gyro.gyro_high_pass_filter = true;
+    config.accel_gyro.accel_z_axis_enabled = false;
+    config.accel_gyro.accel_bandwidth = accel_gyro::AccelBandWidth::_105Hz;
+    config.accel_gyro.accel_self_test = true;
+    config.magnetometer.sampling_rate = magnetometer::SamplingRate::_80Hz;
+    config.magnetometer.operating_mode = magnetometer::OperatingMode::ContinuousConversion;
+    config.fifo.mode = fifo::FifoMode::Continuous;
+    config.fifo.threshold = 40;
+    config.interrupt_routing.int2.inactivity = true;
+
+    let registers = config.all_registers();
+    let value = |register: Register| {
+        registers
+            .iter()
+            .find(|(reg, _)| core::mem::discriminant(reg) == core::mem::discriminant(&register))
+            .map(|(_, value)| *value)
+            .expect("Register not written")
+    };
+    let values = |list: &[(Register, u8)]| -> std::vec::Vec<u8> {
+        list.iter().map(|(reg, _)| value(*reg)).collect()
+    };
+
+    let decoded = DeviceConfig::default()
+        .with_registers(
+            value(Register::CTRL_REG8),
+            values(&config.accel_gyro.all_registers())
+                .try_into()
+                .unwrap(),
+            values(&config.magnetometer.all_registers(config.spi_mode))
+                .try_into()
+                .unwrap(),
+            values(&config.fifo.all_registers()).try_into().unwrap(),
+            values(&config.interrupt_routing.all_registers())
+                .try_into()
+                .unwrap(),
+        )
+        .expect("Error decoding registers");
+
+    assert!(
+        config.differences(&decoded).is_empty(),
+        "Decoded configuration differs: {:?}",
+        config.differences(&decoded)
+    );
+    assert!(
+        decoded.magnetometer.operating_mode == magnetometer::OperatingMode::ContinuousConversion,
+        "Wrong magnetometer operating mode"
+    );
+}
+
+#[test]
+fn config_differences() {
+    let config = DeviceConfig::default();
+    let mut other = DeviceConfig::default();
+    other.accel_gyro.gyro_full_scale = accel_gyro::GyroFullScale::_500dps;
+    other.magnetometer.operating_mode = magnetometer::OperatingMode::ContinuousConversion;
+    other.interrupt_pin_mode = InterruptPinMode::OpenDrain;
+    other.fifo.threshold = 33;
+    other.fifo.mode = fifo::FifoMode::Fifo;
+
+    assert_eq!(
+        config.differences(&other).as_slice(),
+        [
+            "interrupt_pin_mode",
+            "accel_gyro.gyro_full_scale",
+            "magnetometer.operating_mode",
+            "fifo.mode",
+            "fifo.threshold",
+        ],
+        "Wrong differences"
+    );
+
+    assert!(
+        DeviceConfig::default()
+            .with_registers(0, [0xff; 8], [0; 11], [0; 2], [0; 2])
+            .is_none(),
+        "Reserved sampling rate decoded"
+    );
+}
@@ -1,12 +1,14 @@
 use core::slice;
 
-use embedded_hal::i2c::Error as _;
 use embedded_hal::i2c::I2c;
 
 use crate::interface::Interface;
 use crate::registers::{ComponentAddress, Register};
 use crate::Lsm9ds1Error;
 
+/// Set in the sub-address to read multiple magnetometer registers in sequence.
+const M_AUTO_INCREMENT_BIT: u8 = 0x80;
+
 /// I2C address of the Accelerometer/Gyroscope component.
 pub enum AddressAg {
     /// Address is 0x6a.
@@ -23,7 +25,7 @@ pub enum AddressM {
     _0x1e,
 }
 
-trait Address {
+pub(crate) trait Address {
     fn addr(&self) -> u8;
 }
 
@@ -70,7 +72,7 @@ pub struct I2cInterface<I2C> {
     config: Config,
 }
 
-impl<I2C: I2c> I2cInterface<I2C> {
+impl<I2C> I2cInterface<I2C> {
     /// Create a new I2C interface.
     pub fn new(i2c: I2C, config: Config) -> Self {
         Self { bus: i2c, config }
@@ -81,46 +83,102 @@ impl<I2C: I2c> I2cInterface<I2C> {
     pub fn bus(&self) -> &I2C {
         &self.bus
     }
-}
 
-impl<I2C: I2c> Interface for I2cInterface<I2C> {
-    fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error> {
-        let addr = reg.addr();
-        let (device_addr, reg_addr) = match addr {
+    /// Get the I2C address of the component a register belongs to, and the register address.
+    fn device_address(&self, reg: Register) -> (u8, u8) {
+        match reg.addr() {
             ComponentAddress::Ag(reg) => (self.config.addr_ag.addr(), reg),
             ComponentAddress::M(reg) => (self.config.addr_m.addr(), reg),
-        };
+        }
+    }
+
+    /// Get the I2C address and sub-address to read `len` registers in sequence, starting at `reg`.
+    /// The magnetometer only increments the register address if the MSB of the sub-address is set.
+    fn read_address(&self, reg: Register, len: usize) -> (u8, u8) {
+        let (device_addr, reg_addr) = self.device_address(reg);
+
+        match reg.addr() {
+            ComponentAddress::M(_) if len > 1 => (device_addr, reg_addr | M_AUTO_INCREMENT_BIT),
+            _ => (device_addr, reg_addr),
+        }
+    }
+}
+
+impl<I2C: I2c> Interface for I2cInterface<I2C> {
+    type BusError = I2C::Error;
+
+    fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, reg_addr) = self.device_address(reg);
         let mut buf: u8 = 0;
 
         self.bus
             .write_read(device_addr, &[reg_addr], slice::from_mut(&mut buf))
-            .map_err(|e| Lsm9ds1Error::I2cError(e.kind()))?;
+            .map_err(Lsm9ds1Error::Bus)?;
 
         Ok(buf)
     }
 
-    fn read_multiple(&mut self, start_reg: Register, buffer: &mut[u8]) -> Result<(), Lsm9ds1Error> {
-        let start_addr = start_reg.addr();
-        let (device_addr, start_reg_addr) = match start_addr {
-            ComponentAddress::Ag(reg) => (self.config.addr_ag.addr(), reg),
-            ComponentAddress::M(reg) => (self.config.addr_m.addr(), reg),
-        };
+    fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, start_reg_addr) = self.read_address(start_reg, buffer.len());
 
         self.bus
             .write_read(device_addr, &[start_reg_addr], buffer)
-            .map_err(|e| Lsm9ds1Error::I2cError(e.kind()))
+            .map_err(Lsm9ds1Error::Bus)
     }
 
-    fn write(&mut self, reg: Register, value: u8) -> Result<(), Lsm9ds1Error> {
-        let addr = reg.addr();
-        let (device_addr, reg_addr) = match addr {
-            ComponentAddress::Ag(reg) => (self.config.addr_ag.addr(), reg),
-            ComponentAddress::M(reg) => (self.config.addr_m.addr(), reg),
-        };
+    fn write(&mut self, reg: Register, value: u8) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, reg_addr) = self.device_address(reg);
 
         self.bus
             .write(device_addr, &[reg_addr, value])
-            .map_err(|e| Lsm9ds1Error::I2cError(e.kind()))
+            .map_err(Lsm9ds1Error::Bus)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C: embedded_hal_async::i2c::I2c> crate::interface::AsyncInterface for I2cInterface<I2C> {
+    type BusError = I2C::Error;
+
+    async fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, reg_addr) = self.device_address(reg);
+        let mut buf: u8 = 0;
+
+        self.bus
+            .write_read(device_addr, &[reg_addr], slice::from_mut(&mut buf))
+            .await
+            .map_err(Lsm9ds1Error::Bus)?;
+
+        Ok(buf)
+    }
+
+    async fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, start_reg_addr) = self.read_address(start_reg, buffer.len());
+
+        self.bus
+            .write_read(device_addr, &[start_reg_addr], buffer)
+            .await
+            .map_err(Lsm9ds1Error::Bus)
+    }
+
+    async fn write(
+        &mut self,
+        reg: Register,
+        value: u8,
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        let (device_addr, reg_addr) = self.device_address(reg);
+
+        self.bus
+            .write(device_addr, &[reg_addr, value])
+            .await
+            .map_err(Lsm9ds1Error::Bus)
     }
 }
 
@@ -167,4 +225,52 @@ fn write_reg_ag() {
     );
 }
 
-// TODO: Tests for Magnetometer registers
+#[test]
+fn read_reg_m() {
+    let i2c = DummyI2c::new();
+    let mut interface = I2cInterface::new(i2c, Config::default());
+
+    let _ = interface
+        .read(Register::WHO_AM_I_M)
+        .expect("Error in I2C interface");
+
+    assert_eq!(interface.bus().last_addr(), 0x1e, "Wrong I2C address");
+    assert_eq!(interface.bus().rx(), vec![0x0f], "Wrong register address");
+}
+
+#[test]
+fn read_multiple_m_auto_increments() {
+    let i2c = DummyI2c::new();
+    let mut interface = I2cInterface::new(i2c, Config::default());
+
+    let mut buf = [0; 6];
+    interface
+        .read_multiple(Register::OUT_X_L_M, &mut buf)
+        .expect("Error in I2C interface");
+
+    assert_eq!(interface.bus().last_addr(), 0x1e, "Wrong I2C address");
+    assert_eq!(
+        interface.bus().rx(),
+        vec![0xa8],
+        "Auto-increment not requested"
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn read_reg_m_async() {
+    use crate::tests::block_on;
+
+    let i2c = DummyI2c::new_with_tx(&[0x3d]);
+    let mut interface = I2cInterface::new(i2c, Config::default());
+
+    let value = block_on(crate::interface::AsyncInterface::read(
+        &mut interface,
+        Register::WHO_AM_I_M,
+    ))
+    .expect("Error in I2C interface");
+
+    assert_eq!(value, 0x3d, "Wrong value read");
+    assert_eq!(interface.bus().last_addr(), 0x1e, "Wrong I2C address");
+    assert_eq!(interface.bus().rx(), vec![0x0f], "Wrong register address");
+}
@@ -1,18 +1,60 @@
+use crate::config::SPIMode;
 use crate::registers::Register;
 use crate::Lsm9ds1Error;
 
 pub mod i2c;
+pub mod spi;
 
 /// An interface to the sensor.
 pub trait Interface {
+    /// Error reported by the underlying bus.
+    type BusError;
+
     /// Write a value to a register.
-    fn write(&mut self, reg: Register, value: u8) -> Result<(), Lsm9ds1Error>;
+    fn write(&mut self, reg: Register, value: u8) -> Result<(), Lsm9ds1Error<Self::BusError>>;
 
     /// Read a value from a register.
-    fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error>;
+    fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>>;
 
     /// Read multiple values from registers in sequence.
-    fn read_multiple(&mut self, start_reg: Register, buffer: &mut [u8]) -> Result<(), Lsm9ds1Error>;
+    fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>>;
+
+    /// SPI mode the sensor has to be configured for to communicate over this interface, if any.
+    fn spi_mode(&self) -> Option<SPIMode> {
+        None
+    }
+}
+
+/// An asynchronous interface to the sensor.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncInterface {
+    /// Error reported by the underlying bus.
+    type BusError;
+
+    /// Write a value to a register.
+    async fn write(&mut self, reg: Register, value: u8)
+        -> Result<(), Lsm9ds1Error<Self::BusError>>;
+
+    /// Read a value from a register.
+    async fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>>;
+
+    /// Read multiple values from registers in sequence.
+    async fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>>;
+
+    /// SPI mode the sensor has to be configured for to communicate over this interface, if any.
+    fn spi_mode(&self) -> Option<SPIMode> {
+        None
+    }
 }
 
 pub use i2c::I2cInterface;
+pub use spi::SpiInterface;
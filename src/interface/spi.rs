@@ -0,0 +1,253 @@
+use embedded_hal::spi::{Operation, SpiDevice};
+
+use crate::config::SPIMode;
+use crate::interface::Interface;
+use crate::registers::{ComponentAddress, Register};
+use crate::Lsm9ds1Error;
+
+/// Set in the address byte to read from a register.
+const READ_BIT: u8 = 0b1000_0000;
+/// Set in the magnetometer address byte to auto-increment the register address.
+const M_AUTO_INCREMENT_BIT: u8 = 0b0100_0000;
+
+/// SPI Interface to sensor.
+///
+/// The Accelerometer/Gyroscope and Magnetometer components have separate chip select lines, so
+/// each of them is accessed through its own SPI device. Register accesses only use separate write
+/// and read operations, so half-duplex SPI devices are supported.
+pub struct SpiInterface<AG, M> {
+    /// SPI device of the Accelerometer/Gyroscope component.
+    spi_ag: AG,
+    /// SPI device of the Magnetometer component.
+    spi_m: M,
+    /// Wiring of the SPI data lines.
+    mode: SPIMode,
+}
+
+/// Get the address byte to start reading at a register.
+fn read_address(reg: u8) -> u8 {
+    reg | READ_BIT
+}
+
+/// Get the magnetometer address byte to start reading at a register.
+fn read_address_m(reg: u8, len: usize) -> u8 {
+    if len > 1 {
+        reg | READ_BIT | M_AUTO_INCREMENT_BIT
+    } else {
+        reg | READ_BIT
+    }
+}
+
+impl<AG, M> SpiInterface<AG, M> {
+    /// Create a new 4-wire SPI interface, using separate SDI and SDO lines.
+    pub fn new(spi_ag: AG, spi_m: M) -> Self {
+        Self {
+            spi_ag,
+            spi_m,
+            mode: SPIMode::FourWire,
+        }
+    }
+
+    /// Create a new 3-wire SPI interface, using a shared SDI/SDO line.
+    ///
+    /// The sensor only drives the shared line once it has been switched to 3-wire mode, so the
+    /// driver configures both components accordingly during initialization.
+    pub fn new_three_wire(spi_ag: AG, spi_m: M) -> Self {
+        Self {
+            spi_ag,
+            spi_m,
+            mode: SPIMode::ThreeWire,
+        }
+    }
+
+    /// Utility function used by tests to inspect the Accelerometer/Gyroscope device.
+    #[cfg(test)]
+    pub fn spi_ag(&self) -> &AG {
+        &self.spi_ag
+    }
+
+    /// Utility function used by tests to inspect the Magnetometer device.
+    #[cfg(test)]
+    pub fn spi_m(&self) -> &M {
+        &self.spi_m
+    }
+}
+
+impl<AG: SpiDevice, M: SpiDevice<Error = AG::Error>> SpiInterface<AG, M> {
+    /// Run a write followed by a read on the device a register belongs to.
+    fn write_read(
+        &mut self,
+        address: ComponentAddress,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<AG::Error>> {
+        match address {
+            ComponentAddress::Ag(reg) => {
+                let address = read_address(reg);
+                self.spi_ag
+                    .transaction(&mut [Operation::Write(&[address]), Operation::Read(buffer)])
+                    .map_err(Lsm9ds1Error::Bus)
+            }
+            ComponentAddress::M(reg) => {
+                let address = read_address_m(reg, buffer.len());
+                self.spi_m
+                    .transaction(&mut [Operation::Write(&[address]), Operation::Read(buffer)])
+                    .map_err(Lsm9ds1Error::Bus)
+            }
+        }
+    }
+}
+
+impl<AG: SpiDevice, M: SpiDevice<Error = AG::Error>> Interface for SpiInterface<AG, M> {
+    type BusError = AG::Error;
+
+    fn spi_mode(&self) -> Option<SPIMode> {
+        Some(self.mode)
+    }
+
+    fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>> {
+        let mut buf = [0u8];
+        self.write_read(reg.addr(), &mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        self.write_read(start_reg.addr(), buffer)
+    }
+
+    fn write(&mut self, reg: Register, value: u8) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        match reg.addr() {
+            ComponentAddress::Ag(reg) => {
+                self.spi_ag.write(&[reg, value]).map_err(Lsm9ds1Error::Bus)
+            }
+            ComponentAddress::M(reg) => self.spi_m.write(&[reg, value]).map_err(Lsm9ds1Error::Bus),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<AG, M> crate::interface::AsyncInterface for SpiInterface<AG, M>
+where
+    AG: embedded_hal_async::spi::SpiDevice,
+    M: embedded_hal_async::spi::SpiDevice<Error = AG::Error>,
+{
+    type BusError = AG::Error;
+
+    fn spi_mode(&self) -> Option<SPIMode> {
+        Some(self.mode)
+    }
+
+    async fn read(&mut self, reg: Register) -> Result<u8, Lsm9ds1Error<Self::BusError>> {
+        let mut buf = [0u8];
+        self.read_multiple(reg, &mut buf).await?;
+
+        Ok(buf[0])
+    }
+
+    async fn read_multiple(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        match start_reg.addr() {
+            ComponentAddress::Ag(reg) => {
+                let address = read_address(reg);
+                self.spi_ag
+                    .transaction(&mut [Operation::Write(&[address]), Operation::Read(buffer)])
+                    .await
+                    .map_err(Lsm9ds1Error::Bus)
+            }
+            ComponentAddress::M(reg) => {
+                let address = read_address_m(reg, buffer.len());
+                self.spi_m
+                    .transaction(&mut [Operation::Write(&[address]), Operation::Read(buffer)])
+                    .await
+                    .map_err(Lsm9ds1Error::Bus)
+            }
+        }
+    }
+
+    async fn write(
+        &mut self,
+        reg: Register,
+        value: u8,
+    ) -> Result<(), Lsm9ds1Error<Self::BusError>> {
+        match reg.addr() {
+            ComponentAddress::Ag(reg) => self
+                .spi_ag
+                .write(&[reg, value])
+                .await
+                .map_err(Lsm9ds1Error::Bus),
+            ComponentAddress::M(reg) => self
+                .spi_m
+                .write(&[reg, value])
+                .await
+                .map_err(Lsm9ds1Error::Bus),
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::tests::dummy_spi::DummySpi;
+
+#[test]
+fn read_reg_ag() {
+    let mut interface = SpiInterface::new(DummySpi::new_with_tx(&[0x68]), DummySpi::new());
+
+    let value = interface
+        .read(Register::WHO_AM_I)
+        .expect("Error in SPI interface");
+
+    assert_eq!(value, 0x68, "Wrong value read");
+    assert_eq!(interface.spi_ag().rx(), vec![0x8f], "Wrong address byte");
+    assert!(
+        interface.spi_m().rx().is_empty(),
+        "Magnetometer was accessed"
+    );
+}
+
+#[test]
+fn write_reg_m() {
+    let mut interface = SpiInterface::new(DummySpi::new(), DummySpi::new());
+
+    interface
+        .write(Register::CTRL_REG1_M, 0xaa)
+        .expect("Error in SPI interface");
+
+    assert_eq!(
+        interface.spi_m().rx(),
+        vec![0x20, 0xaa],
+        "Wrong register and/or data"
+    );
+    assert!(
+        interface.spi_ag().rx().is_empty(),
+        "Accel/Gyro was accessed"
+    );
+}
+
+#[test]
+fn read_multiple_m_auto_increments() {
+    let mut interface = SpiInterface::new(DummySpi::new(), DummySpi::new_with_tx(&[1, 2, 3]));
+    let mut buf = [0u8; 6];
+
+    interface
+        .read_multiple(Register::OUT_X_L_M, &mut buf)
+        .expect("Error in SPI interface");
+
+    assert_eq!(interface.spi_m().rx(), vec![0xe8], "Wrong address byte");
+    assert_eq!(buf, [1, 2, 3, 1, 2, 3], "Wrong data read");
+}
+
+#[test]
+fn three_wire_mode() {
+    let interface = SpiInterface::new_three_wire(DummySpi::new(), DummySpi::new());
+
+    assert!(
+        matches!(interface.spi_mode(), Some(SPIMode::ThreeWire)),
+        "Wrong SPI mode"
+    );
+}
@@ -2,58 +2,524 @@
 
 /// Builder for an LSM9DS1 sensor.
 pub mod builder;
+/// Sensor calibration.
+pub mod calibration;
 /// Sensor configuration.
 pub mod config;
 /// Sensor interfaces.
 pub mod interface;
+/// Measurement types.
+pub mod measurements;
+
+/// Asynchronous driver.
+#[cfg(feature = "async")]
+pub mod asynch;
+
+/// Simulated sensor for host-side testing.
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
+
+/// Self-test procedures.
+pub mod self_test;
+/// Device status.
+pub mod status;
 
 mod registers;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "async")]
+pub use asynch::Lsm9ds1Async;
 pub use builder::Lsm9ds1Builder;
-use config::DeviceConfig;
+use calibration::temperature::GyroTemperatureFitter;
+use calibration::{AxisStatistics, Calibration, GyroCalibration};
+use config::accel_gyro::{AccelFullScale, AccelGyroConfig, AccelGyroSamplingRate, GyroFullScale};
+use config::activity::{ActivityState, InactivityConfig};
+use config::fifo::{FifoConfig, FifoStatus};
+use config::interrupts::{
+    AccelInterruptConfig, GyroInterruptConfig, Int1Routing, Int2Routing, InterruptSource,
+    MagInterruptConfig, MagInterruptSource,
+};
+use config::magnetometer::{
+    FullScale as MagFullScale, OperatingMode as MagOperatingMode, SamplingRate as MagSamplingRate,
+};
+use config::{ConfigDifferences, DeviceConfig, InterruptPinMode, SPIMode};
 use core::error::Error;
-use core::fmt::Display;
-use embedded_hal::i2c;
+use core::fmt::{Debug, Display};
+use embedded_hal::delay::DelayNs;
 use interface::Interface;
+use measurements::{Acceleration, AngularRate, FifoSample, MagneticField, RawSample};
 use registers::Register;
+use self_test::{
+    SelfTestReport, SensorSelfTest, ACCEL_SELF_TEST_LIMITS, GYRO_SELF_TEST_LIMITS,
+    MAG_SELF_TEST_LIMITS, SELF_TEST_SAMPLES,
+};
+use status::Status;
 
-/// Driver Errors.
+/// Time to wait for the outputs to settle after changing the self-test configuration.
+const SELF_TEST_SETTLING_MS: u32 = 200;
+/// Time to wait between self-test samples, longer than one sample period at 238Hz.
+const SELF_TEST_SAMPLE_PERIOD_MS: u32 = 5;
+/// Time to wait between magnetometer self-test samples, one sample period at 20Hz.
+const MAG_SELF_TEST_SAMPLE_PERIOD_MS: u32 = 50;
+/// Time to wait between polls of the data ready flags.
+const DATA_READY_POLL_INTERVAL_US: u32 = 500;
+/// Number of data ready polls before giving up, longer than one sample period at 14.9Hz.
+const DATA_READY_TIMEOUT_POLLS: u32 = 200;
+
+/// Expected WHO_AM_I value of the accelerometer and gyroscope.
+pub const DEVICE_ID_AG: u8 = 0x68;
+/// Expected WHO_AM_I_M value of the magnetometer.
+pub const DEVICE_ID_M: u8 = 0x3d;
+
+/// Sensor component, each of which is a separate die with its own bus address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Accelerometer and gyroscope (also temperature).
+    AccelGyro,
+    /// Magnetometer.
+    Magnetometer,
+}
+
+/// Driver Errors, generic over the error type `E` of the underlying bus. Implements `Error`, with
+/// the bus error as source, if `E` does; `Display` only requires `E: Debug`.
 #[derive(Debug)]
-pub enum Lsm9ds1Error {
-    /// Error during I2C communication.
-    I2cError(i2c::ErrorKind),
+pub enum Lsm9ds1Error<E> {
+    /// Error during bus communication.
+    Bus(E),
+    /// A register read from the device holds a reserved value.
+    InvalidRegisterValue,
+    /// The identification register of a component doesn't hold the expected value.
+    WrongDeviceId {
+        /// Component that was identified.
+        component: Component,
+        /// Value read from the identification register.
+        found: u8,
+    },
+    /// No new sample became available in time, e.g. because the sensor is powered down.
+    DataNotReady,
+    /// The device moved during a calibration.
+    MotionDetected,
 }
 
-impl Display for Lsm9ds1Error {
+impl<E: Debug> Display for Lsm9ds1Error<E> {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Self::Bus(e) => write!(f, "Bus error: {:?}", e),
+            Self::InvalidRegisterValue => write!(f, "Reserved value read from a register"),
+            Self::WrongDeviceId { component, found } => {
+                write!(f, "Wrong device ID {:#04x} for {:?}", found, component)
+            }
+            Self::DataNotReady => write!(f, "No new sample available"),
+            Self::MotionDetected => write!(f, "Motion detected during calibration"),
+        }
     }
 }
 
-impl Error for Lsm9ds1Error {}
+impl<E: Error + 'static> Error for Lsm9ds1Error<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Bus(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 /// An LSM9DS1 sensor.
 pub struct Lsm9ds1<I: Interface> {
     interface: I,
     config: DeviceConfig,
+    calibration: Calibration,
 }
 
 impl<I: Interface> Lsm9ds1<I> {
     /// Initialize the device by applying all settings.
-    pub fn init(&mut self) -> Result<(), Lsm9ds1Error> {
-        let register_values = self.config.all_registers();
-        for (reg, value) in register_values.into_iter() {
+    pub fn init(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let mut config = self.config.clone();
+        if let Some(spi_mode) = self.interface.spi_mode() {
+            config.spi_mode = spi_mode;
+        }
+
+        for (reg, value) in config.all_registers().into_iter() {
             self.interface.write(reg, value)?;
         }
 
+        self.config = config;
+
         Ok(())
     }
 
+    /// Read the control registers back from the device and decode them. Interrupt generator and
+    /// inactivity settings are not read back, and are taken from the current configuration.
+    pub fn read_config(&mut self) -> Result<DeviceConfig, Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_8 = self.interface.read(Register::CTRL_REG8)?;
+        let accel_gyro = self.read_registers(self.config.accel_gyro.all_registers())?;
+        let magnetometer =
+            self.read_registers(self.config.magnetometer.all_registers(self.config.spi_mode))?;
+        let fifo = self.read_registers(self.config.fifo.all_registers())?;
+        let interrupt_routing =
+            self.read_registers(self.config.interrupt_routing.all_registers())?;
+
+        self.config
+            .with_registers(
+                ctrl_reg_8,
+                accel_gyro,
+                magnetometer,
+                fifo,
+                interrupt_routing,
+            )
+            .ok_or(Lsm9ds1Error::InvalidRegisterValue)
+    }
+
+    /// Compare the configuration of the device with the one applied by the driver, e.g. to detect
+    /// a brown-out reset or writes by another bus master. Returns the paths of all differing
+    /// fields, which is empty if the configuration matches.
+    pub fn verify_config(&mut self) -> Result<ConfigDifferences, Lsm9ds1Error<I::BusError>> {
+        let device_config = self.read_config()?;
+
+        Ok(self.config.differences(&device_config))
+    }
+
+    /// Run the accelerometer and gyroscope self-test. Both sensors are sampled at 238Hz, ±2g and
+    /// ±2000dps, first without and then with self-test enabled. The change of the averaged
+    /// outputs is compared against the datasheet limits for each axis. The device must be kept
+    /// still during the test. The accelerometer and gyroscope configuration is restored
+    /// afterwards.
+    pub fn self_test<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<SelfTestReport, Lsm9ds1Error<I::BusError>> {
+        let report = self.run_self_test(delay);
+        let restored = self.write_registers(self.config.accel_gyro.all_registers());
+
+        let report = report?;
+        restored?;
+
+        Ok(report)
+    }
+
+    fn run_self_test<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<SelfTestReport, Lsm9ds1Error<I::BusError>> {
+        let mut test_config = AccelGyroConfig::default();
+        test_config.accel_gyro_sampling_rate = AccelGyroSamplingRate::_238Hz;
+        test_config.gyro_full_scale = GyroFullScale::_2000dps;
+        test_config.accel_full_scale = AccelFullScale::PlusMinus2g;
+        self.write_registers(test_config.all_registers())?;
+
+        delay.delay_ms(SELF_TEST_SETTLING_MS);
+        let [accel_baseline, gyro_baseline] = self.average_self_test_samples(delay)?;
+
+        self.interface
+            .write(Register::CTRL_REG10, registers::ctrl_reg_10(true, true))?;
+
+        delay.delay_ms(SELF_TEST_SETTLING_MS);
+        let [accel_self_test, gyro_self_test] = self.average_self_test_samples(delay)?;
+
+        Ok(SelfTestReport {
+            accelerometer: SensorSelfTest::new(
+                accel_baseline,
+                accel_self_test,
+                ACCEL_SELF_TEST_LIMITS,
+            ),
+            gyroscope: SensorSelfTest::new(gyro_baseline, gyro_self_test, GYRO_SELF_TEST_LIMITS),
+        })
+    }
+
+    /// Average accelerometer (g) and gyroscope (dps) samples at the self-test full scales,
+    /// discarding the first sample. Returns `[acceleration, angular_rate]`.
+    fn average_self_test_samples<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<[[f32; 3]; 2], Lsm9ds1Error<I::BusError>> {
+        self.read_accelerometer_raw()?;
+        self.read_gyroscope_raw()?;
+
+        let mut acceleration = [0.0; 3];
+        let mut angular_rate = [0.0; 3];
+        for _ in 0..SELF_TEST_SAMPLES {
+            delay.delay_ms(SELF_TEST_SAMPLE_PERIOD_MS);
+
+            let sample = Acceleration::from_raw(
+                self.read_accelerometer_raw()?,
+                AccelFullScale::PlusMinus2g.sensitivity(),
+            );
+            for (sum, value) in acceleration.iter_mut().zip([sample.x, sample.y, sample.z]) {
+                *sum += value / SELF_TEST_SAMPLES as f32;
+            }
+
+            let sample = AngularRate::from_raw(
+                self.read_gyroscope_raw()?,
+                GyroFullScale::_2000dps.sensitivity(),
+            );
+            for (sum, value) in angular_rate.iter_mut().zip([sample.x, sample.y, sample.z]) {
+                *sum += value / SELF_TEST_SAMPLES as f32;
+            }
+        }
+
+        Ok([acceleration, angular_rate])
+    }
+
+    /// Run the magnetometer self-test. The magnetometer is sampled at 20Hz and ±12 gauss, first
+    /// without and then with self-test enabled. The change of the averaged outputs is compared
+    /// against the datasheet limits for each axis. The device must be kept away from changing
+    /// magnetic fields during the test. The magnetometer configuration is restored afterwards.
+    pub fn magnetometer_self_test<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<SensorSelfTest, Lsm9ds1Error<I::BusError>> {
+        let result = self.run_magnetometer_self_test(delay);
+        let restored =
+            self.write_registers(self.config.magnetometer.all_registers(self.config.spi_mode));
+
+        let result = result?;
+        restored?;
+
+        Ok(result)
+    }
+
+    fn run_magnetometer_self_test<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<SensorSelfTest, Lsm9ds1Error<I::BusError>> {
+        // Bus related settings are kept, so the magnetometer stays accessible.
+        let mut test_config = self.config.magnetometer.clone();
+        test_config.full_scale = MagFullScale::PlusMinus12Gauss;
+        test_config.sampling_rate = MagSamplingRate::_20Hz;
+        test_config.fast_sampling = false;
+        test_config.low_power_mode = false;
+        test_config.operating_mode = MagOperatingMode::ContinuousConversion;
+        test_config.self_test = false;
+        self.write_registers(test_config.all_registers(self.config.spi_mode))?;
+
+        delay.delay_ms(SELF_TEST_SETTLING_MS);
+        let baseline = self.average_magnetometer_self_test_samples(delay)?;
+
+        let ctrl_reg_1_m = registers::ctrl_reg_1_m(
+            test_config.temperature_compensation,
+            test_config.xy_performance_mode,
+            test_config.sampling_rate,
+            test_config.fast_sampling,
+            true,
+        );
+        self.interface.write(Register::CTRL_REG1_M, ctrl_reg_1_m)?;
+
+        delay.delay_ms(SELF_TEST_SETTLING_MS);
+        let self_test = self.average_magnetometer_self_test_samples(delay)?;
+
+        Ok(SensorSelfTest::with_axis_limits(
+            baseline,
+            self_test,
+            MAG_SELF_TEST_LIMITS,
+        ))
+    }
+
+    /// Average magnetometer samples (gauss) at the self-test full scale, discarding the first
+    /// sample.
+    fn average_magnetometer_self_test_samples<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<[f32; 3], Lsm9ds1Error<I::BusError>> {
+        self.read_magnetometer_raw()?;
+
+        let mut magnetic_field = [0.0; 3];
+        for _ in 0..SELF_TEST_SAMPLES {
+            delay.delay_ms(MAG_SELF_TEST_SAMPLE_PERIOD_MS);
+
+            let sample = MagneticField::from_raw(
+                self.read_magnetometer_raw()?,
+                MagFullScale::PlusMinus12Gauss.sensitivity(),
+            );
+            for (sum, value) in magnetic_field
+                .iter_mut()
+                .zip([sample.x, sample.y, sample.z])
+            {
+                *sum += value / SELF_TEST_SAMPLES as f32;
+            }
+        }
+
+        Ok(magnetic_field)
+    }
+
+    /// Calibration applied to the sensor readings.
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Replace the calibration applied to the sensor readings, e.g. with a stored one.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Determine the gyroscope zero-rate bias from `samples` readings at the current sampling
+    /// rate and full scale. The device must be kept still: the run is rejected with
+    /// `Lsm9ds1Error::MotionDetected` if the variance of any axis exceeds `variance_limit` (in
+    /// dps²). On success the calibration is stored and subtracted from all following angular
+    /// rate readings.
+    pub fn calibrate_gyroscope<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        variance_limit: f32,
+    ) -> Result<GyroCalibration, Lsm9ds1Error<I::BusError>> {
+        let calibration = GyroCalibration {
+            bias: self.average_angular_rate(delay, samples, variance_limit)?,
+        };
+        self.calibration.gyro = Some(calibration);
+
+        Ok(calibration)
+    }
+
+    /// Record the gyroscope zero-rate bias at the current temperature into `fitter`, as one
+    /// step of a thermal sweep. The bias is determined like in `calibrate_gyroscope`, and the
+    /// temperature is averaged over the run. Returns the temperature in °C.
+    pub fn record_gyro_bias<D: DelayNs>(
+        &mut self,
+        fitter: &mut GyroTemperatureFitter,
+        delay: &mut D,
+        samples: u16,
+        variance_limit: f32,
+    ) -> Result<f32, Lsm9ds1Error<I::BusError>> {
+        let start_c = self.temperature_c()?;
+        let bias = self.average_angular_rate(delay, samples, variance_limit)?;
+        let temperature_c = (start_c + self.temperature_c()?) / 2.0;
+
+        fitter.push(temperature_c, bias);
+
+        Ok(temperature_c)
+    }
+
+    /// Average `samples` angular rate readings without calibration applied, and reject them if
+    /// the variance of any axis exceeds `variance_limit` (in dps²).
+    fn average_angular_rate<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        variance_limit: f32,
+    ) -> Result<AngularRate, Lsm9ds1Error<I::BusError>> {
+        let [x, y, z] = self.average_at_rest(
+            delay,
+            samples,
+            variance_limit,
+            |status| status.gyro_data_available,
+            |device| {
+                let sample = AngularRate::from_raw(
+                    device.read_gyroscope_raw()?,
+                    device.config.accel_gyro.gyro_full_scale.sensitivity(),
+                );
+                Ok([sample.x, sample.y, sample.z])
+            },
+        )?;
+
+        Ok(AngularRate { x, y, z })
+    }
+
+    /// Average `samples` accelerometer readings without calibration applied, e.g. to record an
+    /// orientation for an `AccelCalibrator`. The device must be kept still: the run is rejected
+    /// with `Lsm9ds1Error::MotionDetected` if the variance of any axis exceeds `variance_limit`
+    /// (in g²).
+    pub fn average_acceleration<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        variance_limit: f32,
+    ) -> Result<Acceleration, Lsm9ds1Error<I::BusError>> {
+        let [x, y, z] = self.average_at_rest(
+            delay,
+            samples,
+            variance_limit,
+            |status| status.accel_data_available,
+            |device| {
+                let sample = Acceleration::from_raw(
+                    device.read_accelerometer_raw()?,
+                    device.config.accel_gyro.accel_full_scale.sensitivity(),
+                );
+                Ok([sample.x, sample.y, sample.z])
+            },
+        )?;
+
+        Ok(Acceleration { x, y, z })
+    }
+
+    /// Average `samples` new `[x, y, z]` readings, and reject them if the variance of any axis
+    /// exceeds `variance_limit`.
+    fn average_at_rest<D, F>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        variance_limit: f32,
+        data_available: fn(&Status) -> bool,
+        read: F,
+    ) -> Result<[f32; 3], Lsm9ds1Error<I::BusError>>
+    where
+        D: DelayNs,
+        F: Fn(&mut Self) -> Result<[f32; 3], Lsm9ds1Error<I::BusError>>,
+    {
+        let mut statistics = AxisStatistics::default();
+        for _ in 0..samples.max(1) {
+            self.wait_for_data(delay, data_available)?;
+            statistics.push(read(self)?);
+        }
+
+        if statistics
+            .variance()
+            .iter()
+            .any(|&variance| variance > variance_limit)
+        {
+            return Err(Lsm9ds1Error::MotionDetected);
+        }
+
+        Ok(statistics.mean())
+    }
+
+    /// Poll the status until `data_available` reports a new sample.
+    fn wait_for_data<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        data_available: fn(&Status) -> bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        for _ in 0..DATA_READY_TIMEOUT_POLLS {
+            if data_available(&self.status()?) {
+                return Ok(());
+            }
+            delay.delay_us(DATA_READY_POLL_INTERVAL_US);
+        }
+
+        Err(Lsm9ds1Error::DataNotReady)
+    }
+
+    /// Write the given register values.
+    fn write_registers<const N: usize>(
+        &mut self,
+        registers: [(Register, u8); N],
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        for (reg, value) in registers {
+            self.interface.write(reg, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the values of the given registers.
+    fn read_registers<const N: usize>(
+        &mut self,
+        registers: [(Register, u8); N],
+    ) -> Result<[u8; N], Lsm9ds1Error<I::BusError>> {
+        let mut values = [0; N];
+        for (value, (reg, _)) in values.iter_mut().zip(registers) {
+            *value = self.interface.read(reg)?;
+        }
+
+        Ok(values)
+    }
+
     /// Enable or disable the accelerometer.
-    pub fn set_accelerometer_enabled(&mut self, enabled: bool) -> Result<(), Lsm9ds1Error> {
+    pub fn set_accelerometer_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
         let ctrl_reg_5_xl = registers::ctrl_reg_5_xl(
             self.config.accel_gyro.accel_decimation,
             enabled,
@@ -72,8 +538,16 @@ impl<I: Interface> Lsm9ds1<I> {
     }
 
     /// Enable or disable the gyroscope.
-    pub fn set_gyroscope_enabled(&mut self, enabled: bool) -> Result<(), Lsm9ds1Error> {
-        let ctrl_reg_4 = registers::ctrl_reg_4(enabled, enabled, enabled);
+    pub fn set_gyroscope_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let ctrl_reg_4 = registers::ctrl_reg_4(
+            enabled,
+            enabled,
+            enabled,
+            self.config.accel_gyro.accel_interrupt_latched,
+        );
 
         self.interface.write(Register::CTRL_REG4, ctrl_reg_4)?;
 
@@ -85,7 +559,10 @@ impl<I: Interface> Lsm9ds1<I> {
     }
 
     /// Enable or disable the magnetometer.
-    pub fn set_magnetometer_enabled(&mut self, enabled: bool) -> Result<(), Lsm9ds1Error> {
+    pub fn set_magnetometer_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
         let operating_mode = if enabled {
             config::magnetometer::OperatingMode::ContinuousConversion
         } else {
@@ -95,7 +572,9 @@ impl<I: Interface> Lsm9ds1<I> {
         let ctrl_reg_3_m = registers::ctrl_rg_3_m(
             self.config.magnetometer.i2c_disabled,
             self.config.magnetometer.low_power_mode,
-            self.config.magnetometer.spi_write_only,
+            self.config
+                .magnetometer
+                .effective_spi_write_only(self.config.spi_mode),
             operating_mode,
         );
 
@@ -110,7 +589,7 @@ impl<I: Interface> Lsm9ds1<I> {
     pub fn set_accel_gyro_sampling_rate(
         &mut self,
         rate: config::accel_gyro::AccelGyroSamplingRate,
-    ) -> Result<(), Lsm9ds1Error> {
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
         let ctrl_reg_1_g = registers::ctrl_reg_1_g(rate, self.config.accel_gyro.gyro_full_scale);
 
         self.interface.write(Register::CTRL_REG1_G, ctrl_reg_1_g)?;
@@ -124,7 +603,7 @@ impl<I: Interface> Lsm9ds1<I> {
     pub fn set_accel_sampling_rate(
         &mut self,
         rate: config::accel_gyro::AccelSamplingRate,
-    ) -> Result<(), Lsm9ds1Error> {
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
         let ctrl_reg_6_xl = registers::ctrl_reg_6_xl(
             rate,
             self.config.accel_gyro.accel_full_scale,
@@ -139,93 +618,890 @@ impl<I: Interface> Lsm9ds1<I> {
         Ok(())
     }
 
-    /// Read out gyroscope data in dps.
-    pub fn get_gyroscope_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error> {
+    /// Set the signals routed to the INT1_A/G pin.
+    pub fn set_int1_routing(
+        &mut self,
+        routing: Int1Routing,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        self.interface
+            .write(Register::INT1_CTRL, registers::int1_ctrl(routing))?;
+
+        self.config.interrupt_routing.int1 = routing;
+
+        Ok(())
+    }
+
+    /// Set the signals routed to the INT2_A/G pin.
+    pub fn set_int2_routing(
+        &mut self,
+        routing: Int2Routing,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        self.interface
+            .write(Register::INT2_CTRL, registers::int2_ctrl(routing))?;
+
+        self.config.interrupt_routing.int2 = routing;
+
+        Ok(())
+    }
+
+    /// Set polarity and output stage of the INT1_A/G and INT2_A/G pins.
+    pub fn set_interrupt_pin_config(
+        &mut self,
+        active_low: bool,
+        pin_mode: InterruptPinMode,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let mut config = self.config.clone();
+        config.interrupt_active_low = active_low;
+        config.interrupt_pin_mode = pin_mode;
+
+        self.interface
+            .write(Register::CTRL_REG8, config.ctrl_reg_8())?;
+
+        self.config = config;
+
+        Ok(())
+    }
+
+    /// Configure the accelerometer interrupt generator. Thresholds are converted using the current
+    /// accelerometer full scale.
+    ///
+    /// On error, the interrupt generator may be partially written; re-run `init()` to restore a
+    /// known state.
+    pub fn set_accel_interrupt(
+        &mut self,
+        accel_interrupt: AccelInterruptConfig,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let register_values =
+            accel_interrupt.all_registers(self.config.accel_gyro.accel_full_scale);
+        for (reg, value) in register_values.into_iter() {
+            self.interface.write(reg, value)?;
+        }
+
+        self.config.accel_interrupt = accel_interrupt;
+
+        Ok(())
+    }
+
+    /// Read out and clear the accelerometer interrupt generator state.
+    pub fn accel_interrupt_source(&mut self) -> Result<InterruptSource, Lsm9ds1Error<I::BusError>> {
+        let int_gen_src_xl = self.interface.read(Register::INT_GEN_SRC_XL)?;
+
+        Ok(registers::int_gen_src(int_gen_src_xl))
+    }
+
+    /// Configure the gyroscope interrupt generator. Thresholds are converted using the current
+    /// gyroscope full scale.
+    ///
+    /// On error, the interrupt generator may be partially written; re-run `init()` to restore a
+    /// known state.
+    pub fn set_gyro_interrupt(
+        &mut self,
+        gyro_interrupt: GyroInterruptConfig,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let register_values = gyro_interrupt.all_registers(self.config.accel_gyro.gyro_full_scale);
+        for (reg, value) in register_values.into_iter() {
+            self.interface.write(reg, value)?;
+        }
+
+        self.config.gyro_interrupt = gyro_interrupt;
+
+        Ok(())
+    }
+
+    /// Read out the gyroscope interrupt generator state. Reading clears latched interrupts.
+    pub fn gyro_interrupt_source(&mut self) -> Result<InterruptSource, Lsm9ds1Error<I::BusError>> {
+        let int_gen_src_g = self.interface.read(Register::INT_GEN_SRC_G)?;
+
+        Ok(registers::int_gen_src(int_gen_src_g))
+    }
+
+    /// Configure the magnetometer interrupt. The threshold is converted using the current
+    /// magnetometer full scale.
+    ///
+    /// On error, the interrupt configuration may be partially written; re-run `init()` to restore a
+    /// known state.
+    pub fn set_magnetometer_interrupt(
+        &mut self,
+        magnetometer_interrupt: MagInterruptConfig,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let register_values =
+            magnetometer_interrupt.all_registers(self.config.magnetometer.full_scale);
+        for (reg, value) in register_values.into_iter() {
+            self.interface.write(reg, value)?;
+        }
+
+        self.config.magnetometer_interrupt = magnetometer_interrupt;
+
+        Ok(())
+    }
+
+    /// Read out the magnetometer interrupt state. Reading clears latched interrupts.
+    pub fn magnetometer_interrupt_source(
+        &mut self,
+    ) -> Result<MagInterruptSource, Lsm9ds1Error<I::BusError>> {
+        let int_src_m = self.interface.read(Register::INT_SRC_M)?;
+
+        Ok(registers::int_src_m(int_src_m))
+    }
+
+    /// Set the hard-iron offset in gauss, which the magnetometer subtracts from its output. The
+    /// offset is converted using the current magnetometer full scale.
+    ///
+    /// On error, the offset registers may be partially written; re-run `init()` to restore a
+    /// known state.
+    pub fn set_hard_iron_offset(
+        &mut self,
+        offset: MagneticField,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let mut magnetometer = self.config.magnetometer.clone();
+        magnetometer.hard_iron_offset = offset;
+
+        let [offset_x, offset_y, offset_z] = magnetometer.offset_registers();
+        self.write_registers([
+            (Register::OFFSET_X_REG_L_M, offset_x.0),
+            (Register::OFFSET_X_REG_H_M, offset_x.1),
+            (Register::OFFSET_Y_REG_L_M, offset_y.0),
+            (Register::OFFSET_Y_REG_H_M, offset_y.1),
+            (Register::OFFSET_Z_REG_L_M, offset_z.0),
+            (Register::OFFSET_Z_REG_H_M, offset_z.1),
+        ])?;
+
+        self.config.magnetometer = magnetometer;
+
+        Ok(())
+    }
+
+    /// Read out the hard-iron offset in gauss from the magnetometer.
+    pub fn read_hard_iron_offset(&mut self) -> Result<MagneticField, Lsm9ds1Error<I::BusError>> {
+        let mut data = [0; 6];
+        self.interface
+            .read_multiple(Register::OFFSET_X_REG_L_M, &mut data)?;
+
+        Ok(MagneticField::from_raw(
+            RawSample::from_le_bytes(data),
+            self.config.magnetometer.full_scale.sensitivity(),
+        ))
+    }
+
+    /// Configure inactivity detection. The threshold is converted using the current accelerometer
+    /// full scale.
+    ///
+    /// On error, the inactivity registers may be partially written; re-run `init()` to restore a
+    /// known state.
+    pub fn set_inactivity(
+        &mut self,
+        inactivity: InactivityConfig,
+    ) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let register_values = inactivity.all_registers(self.config.accel_gyro.accel_full_scale);
+        for (reg, value) in register_values.into_iter() {
+            self.interface.write(reg, value)?;
+        }
+
+        self.config.inactivity = inactivity;
+
+        Ok(())
+    }
+
+    /// Read out the Accelerometer/Gyroscope status.
+    pub fn status(&mut self) -> Result<Status, Lsm9ds1Error<I::BusError>> {
+        let status_reg = self.interface.read(Register::STATUS_REG_A)?;
+
+        Ok(registers::status_reg(status_reg))
+    }
+
+    /// Read out the current activity state.
+    pub fn activity_state(&mut self) -> Result<ActivityState, Lsm9ds1Error<I::BusError>> {
+        Ok(self.status()?.activity_state())
+    }
+
+    /// Configure the FIFO.
+    ///
+    /// On error, the FIFO registers may be partially written; re-run `init()` to restore a known
+    /// state.
+    pub fn set_fifo_config(&mut self, fifo: FifoConfig) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        for (reg, value) in fifo.all_registers().into_iter() {
+            self.interface.write(reg, value)?;
+        }
+
+        self.config.fifo = fifo;
+
+        Ok(())
+    }
+
+    /// Read out the FIFO status.
+    pub fn fifo_status(&mut self) -> Result<FifoStatus, Lsm9ds1Error<I::BusError>> {
+        let fifo_src = self.interface.read(Register::FIFO_SRC)?;
+
+        Ok(registers::fifo_src(fifo_src))
+    }
+
+    /// Read out up to `buffer.len()` samples from the FIFO, oldest first. Returns the number of
+    /// samples read, which is limited by the number of samples stored in the FIFO.
+    ///
+    /// Each sample is read in a single burst of the gyroscope and accelerometer output registers,
+    /// which relies on register address auto-increment (`CTRL_REG8` IF_ADD_INC, enabled by
+    /// default). The calibration is applied to each sample; a temperature dependent gyroscope
+    /// bias model uses a single temperature reading for the whole batch.
+    pub fn read_fifo(
+        &mut self,
+        buffer: &mut [FifoSample],
+    ) -> Result<usize, Lsm9ds1Error<I::BusError>> {
+        let status = self.fifo_status()?;
+        let count = buffer.len().min(status.level as usize);
+        let temperature_c = self.gyro_model_temperature_c()?;
+
+        for sample in buffer[..count].iter_mut() {
+            // The address jumps from the last gyroscope to the first accelerometer output
+            // register, so one burst covers a whole FIFO sample.
+            let mut data = [0; 12];
+            self.interface
+                .read_multiple(Register::OUT_X_L_G, &mut data)?;
+
+            let (gyro, accel) = data.split_at(6);
+            let gyro: [u8; 6] = gyro.try_into().unwrap();
+            let accel: [u8; 6] = accel.try_into().unwrap();
+            sample.angular_rate =
+                self.calibrated_angular_rate(RawSample::from_le_bytes(gyro), temperature_c);
+            sample.acceleration = self.calibrated_acceleration(RawSample::from_le_bytes(accel));
+        }
+
+        Ok(count)
+    }
+
+    /// Read out the raw gyroscope sample.
+    pub fn read_gyroscope_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
         let mut data = [0; 6];
         self.interface
             .read_multiple(Register::OUT_X_L_G, &mut data)?;
 
-        let x_raw = (data[1] as i16) << 8 | data[0] as i16;
-        let y_raw = (data[3] as i16) << 8 | data[2] as i16;
-        let z_raw = (data[5] as i16) << 8 | data[4] as i16;
+        Ok(RawSample::from_le_bytes(data))
+    }
 
-        let scale_factor = f32::from(self.config.accel_gyro.gyro_full_scale) / (i16::MAX as f32);
+    /// Read out the angular rate measured by the gyroscope, with the gyroscope calibration
+    /// applied. A temperature dependent bias model additionally reads out the temperature.
+    pub fn read_angular_rate(&mut self) -> Result<AngularRate, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_gyroscope_raw()?;
+        let temperature_c = self.gyro_model_temperature_c()?;
 
-        let x_dps = x_raw as f32 * scale_factor;
-        let y_dps = y_raw as f32 * scale_factor;
-        let z_dps = z_raw as f32 * scale_factor;
+        Ok(self.calibrated_angular_rate(raw, temperature_c))
+    }
 
-        Ok((x_dps, y_dps, z_dps))
+    /// Read out the temperature if the gyroscope calibration depends on it.
+    fn gyro_model_temperature_c(&mut self) -> Result<Option<f32>, Lsm9ds1Error<I::BusError>> {
+        match self.calibration.gyro_temperature {
+            Some(_) => Ok(Some(self.temperature_c()?)),
+            None => Ok(None),
+        }
     }
 
-    /// Read out accelerometer data in g.
-    pub fn get_accelerometer_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error> {
+    /// Convert a raw gyroscope sample and apply the gyroscope calibration.
+    fn calibrated_angular_rate(&self, raw: RawSample, temperature_c: Option<f32>) -> AngularRate {
+        let angular_rate =
+            AngularRate::from_raw(raw, self.config.accel_gyro.gyro_full_scale.sensitivity());
+
+        match (
+            self.calibration.gyro_temperature.zip(temperature_c),
+            self.calibration.gyro,
+        ) {
+            (Some((model, temperature_c)), _) => model.apply(angular_rate, temperature_c),
+            (None, Some(calibration)) => calibration.apply(angular_rate),
+            (None, None) => angular_rate,
+        }
+    }
+
+    /// Read out gyroscope data in dps.
+    pub fn get_gyroscope_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let angular_rate = self.read_angular_rate()?;
+
+        Ok((angular_rate.x, angular_rate.y, angular_rate.z))
+    }
+
+    /// Read out the raw accelerometer sample.
+    pub fn read_accelerometer_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
         let mut data = [0; 6];
         self.interface
             .read_multiple(Register::OUT_X_L_XL, &mut data)?;
 
-        let x_raw = (data[1] as i16) << 8 | data[0] as i16;
-        let y_raw = (data[3] as i16) << 8 | data[2] as i16;
-        let z_raw = (data[5] as i16) << 8 | data[4] as i16;
+        Ok(RawSample::from_le_bytes(data))
+    }
+
+    /// Read out the acceleration measured by the accelerometer, with the accelerometer
+    /// calibration applied.
+    pub fn read_acceleration(&mut self) -> Result<Acceleration, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_accelerometer_raw()?;
 
-        let scale_factor = f32::from(self.config.accel_gyro.accel_full_scale) / (i16::MAX as f32);
+        Ok(self.calibrated_acceleration(raw))
+    }
 
-        let x_g = x_raw as f32 * scale_factor;
-        let y_g = y_raw as f32 * scale_factor;
-        let z_g = z_raw as f32 * scale_factor;
+    /// Convert a raw accelerometer sample and apply the accelerometer calibration.
+    fn calibrated_acceleration(&self, raw: RawSample) -> Acceleration {
+        let acceleration =
+            Acceleration::from_raw(raw, self.config.accel_gyro.accel_full_scale.sensitivity());
 
-        Ok((x_g, y_g, z_g))
+        match self.calibration.accel {
+            Some(calibration) => calibration.apply(acceleration),
+            None => acceleration,
+        }
     }
 
-    /// Read out magnetometer data in Gauss.
-    pub fn get_magnetometer_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error> {
+    /// Read out accelerometer data in g.
+    pub fn get_accelerometer_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let acceleration = self.read_acceleration()?;
+
+        Ok((acceleration.x, acceleration.y, acceleration.z))
+    }
+
+    /// Read out the raw magnetometer sample.
+    pub fn read_magnetometer_raw(&mut self) -> Result<RawSample, Lsm9ds1Error<I::BusError>> {
         let mut data = [0; 6];
         self.interface
             .read_multiple(Register::OUT_X_L_M, &mut data)?;
 
-        let x_raw = (data[1] as i16) << 8 | data[0] as i16;
-        let y_raw = (data[3] as i16) << 8 | data[2] as i16;
-        let z_raw = (data[5] as i16) << 8 | data[4] as i16;
+        Ok(RawSample::from_le_bytes(data))
+    }
 
-        let scale_factor = f32::from(self.config.magnetometer.full_scale) / (i16::MAX as f32);
+    /// Read out the magnetic field measured by the magnetometer, with the magnetometer
+    /// calibration applied.
+    pub fn read_magnetic_field(&mut self) -> Result<MagneticField, Lsm9ds1Error<I::BusError>> {
+        let raw = self.read_magnetometer_raw()?;
+        let field = MagneticField::from_raw(raw, self.config.magnetometer.full_scale.sensitivity());
 
-        let x_gauss = x_raw as f32 * scale_factor;
-        let y_gauss = y_raw as f32 * scale_factor;
-        let z_gauss = z_raw as f32 * scale_factor;
+        Ok(match self.calibration.mag {
+            Some(calibration) => calibration.apply(field),
+            None => field,
+        })
+    }
 
-        Ok((x_gauss, y_gauss, z_gauss))
+    /// Read out magnetometer data in Gauss.
+    pub fn get_magnetometer_data(&mut self) -> Result<(f32, f32, f32), Lsm9ds1Error<I::BusError>> {
+        let magnetic_field = self.read_magnetic_field()?;
+
+        Ok((magnetic_field.x, magnetic_field.y, magnetic_field.z))
     }
 
     /// Read out chip identification for the accelerometer and gyroscope.
-    pub fn who_am_i_ag(&mut self) -> Result<u8, Lsm9ds1Error> {
+    pub fn who_am_i_ag(&mut self) -> Result<u8, Lsm9ds1Error<I::BusError>> {
         self.interface.read(Register::WHO_AM_I)
     }
 
     /// Read out chip identification for the magnetometer.
-    pub fn who_am_i_m(&mut self) -> Result<u8, Lsm9ds1Error> {
+    pub fn who_am_i_m(&mut self) -> Result<u8, Lsm9ds1Error<I::BusError>> {
         self.interface.read(Register::WHO_AM_I_M)
     }
 
+    /// Switch both components to 3-wire SPI mode if the interface uses it, as the sensor doesn't
+    /// drive the shared data line before. Other settings are left at their power-on values.
+    pub(crate) fn select_spi_mode(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        match self.interface.spi_mode() {
+            Some(SPIMode::ThreeWire) => {
+                self.write_registers(DeviceConfig::spi_mode_registers(SPIMode::ThreeWire))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the chip identification of both components. On a 3-wire SPI interface, the device
+    /// must have been initialized first.
+    pub fn verify_identity(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
+        let found = self.who_am_i_ag()?;
+        if found != DEVICE_ID_AG {
+            return Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::AccelGyro,
+                found,
+            });
+        }
+
+        let found = self.who_am_i_m()?;
+        if found != DEVICE_ID_M {
+            return Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::Magnetometer,
+                found,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Apply software reset.
-    pub fn reset(&mut self) -> Result<(), Lsm9ds1Error> {
+    pub fn reset(&mut self) -> Result<(), Lsm9ds1Error<I::BusError>> {
         let mut ctrl_reg = self.interface.read(Register::CTRL_REG8)?;
         ctrl_reg |= 0b1;
         self.interface.write(Register::CTRL_REG8, ctrl_reg)
     }
 
+    /// Read out the raw temperature sample.
+    pub fn read_temperature_raw(&mut self) -> Result<i16, Lsm9ds1Error<I::BusError>> {
+        let temp_l = self.interface.read(Register::OUT_TEMP_L)?;
+        let temp_h = self.interface.read(Register::OUT_TEMP_H)?;
+
+        Ok(i16::from_le_bytes([temp_l, temp_h]))
+    }
+
     /// Read out temperature in °C. Temperature is used for internal temperature compensation and
     /// not as a primary sensor output. Values will be inaccurate if primary sensor types aren't
     /// being sampled.
-    pub fn temperature_c(&mut self) -> Result<f32, Lsm9ds1Error> {
-        let temp_l = self.interface.read(Register::OUT_TEMP_L)?;
-        let temp_h = self.interface.read(Register::OUT_TEMP_H)?;
+    pub fn temperature_c(&mut self) -> Result<f32, Lsm9ds1Error<I::BusError>> {
+        let temp = self.read_temperature_raw()?;
+
+        Ok(measurements::temperature_c_from_raw(temp))
+    }
+}
+
+#[cfg(test)]
+use crate::{interface::i2c as i2c_interface, tests::dummy_i2c::DummyI2c, tests::NoDelay};
+
+#[test]
+fn self_test_restores_config() {
+    let interface = interface::I2cInterface::new(
+        DummyI2c::new_with_tx(&[0x00, 0x10]),
+        i2c_interface::Config::default(),
+    );
+    let mut device = Lsm9ds1Builder::new()
+        .init_on(interface)
+        .expect("Error during initialization");
+
+    let report = device
+        .self_test(&mut NoDelay)
+        .expect("Error during self-test");
+
+    assert!(!report.passed(), "Self-test passed without output change");
+    assert_eq!(report.accelerometer.x.delta, 0.0, "Wrong output change");
+    assert_eq!(
+        device.interface.bus().rx(),
+        [0x24, 0x00],
+        "Self-test not disabled afterwards"
+    );
+}
+
+#[test]
+fn magnetometer_self_test_restores_config() {
+    use crate::simulator::Lsm9ds1Simulator;
+
+    let ctrl_registers = [
+        Register::CTRL_REG1_M,
+        Register::CTRL_REG2_M,
+        Register::CTRL_REG3_M,
+        Register::CTRL_REG4_M,
+        Register::CTRL_REG5_M,
+    ];
+
+    let mut sim = Lsm9ds1Simulator::new();
+    let mut device = Lsm9ds1Builder::new()
+        .with_magnetometer_scale(MagFullScale::PlusMinus16Gauss)
+        .with_magnetometer_sampling_rate(MagSamplingRate::_80Hz)
+        .init_on(interface::I2cInterface::new(
+            &mut sim,
+            i2c_interface::Config::default(),
+        ))
+        .expect("Error during initialization");
+
+    let mut expected = [0; 5];
+    for (value, reg) in expected.iter_mut().zip(ctrl_registers) {
+        *value = device.interface.read(reg).unwrap();
+    }
+    assert_eq!(expected[1], 0x60, "±16 gauss full scale not applied");
+
+    let result = device
+        .magnetometer_self_test(&mut NoDelay)
+        .expect("Error during self-test");
+    assert!(!result.passed(), "Self-test passed without output change");
+
+    for (i, (expected, reg)) in expected.into_iter().zip(ctrl_registers).enumerate() {
+        assert_eq!(
+            device.interface.read(reg).unwrap(),
+            expected,
+            "CTRL_REG{}_M not restored",
+            i + 1
+        );
+    }
+}
+
+#[test]
+fn gyroscope_calibration_applied() {
+    let interface = interface::I2cInterface::new(
+        DummyI2c::new_with_tx(&[0xff]),
+        i2c_interface::Config::default(),
+    );
+    let mut device = Lsm9ds1Builder::new()
+        .init_on(interface)
+        .expect("Error during initialization");
+
+    let calibration = device
+        .calibrate_gyroscope(&mut NoDelay, 4, 0.0)
+        .expect("Error during calibration");
+
+    let sensitivity = GyroFullScale::_245dps.sensitivity();
+    assert_eq!(calibration.bias.x, -sensitivity, "Wrong bias");
+    assert_eq!(
+        device.calibration().gyro,
+        Some(calibration),
+        "Bias not stored"
+    );
+    assert_eq!(
+        device.get_gyroscope_data().unwrap(),
+        (0.0, 0.0, 0.0),
+        "Bias not subtracted"
+    );
+
+    device.set_calibration(Default::default());
+    assert_eq!(
+        device.read_angular_rate().unwrap().z,
+        -sensitivity,
+        "Bias subtracted without calibration"
+    );
+}
 
-        let temp: i16 = (temp_h as i16) << 8 | temp_l as i16;
+#[test]
+fn identity_check() {
+    let interface = interface::I2cInterface::new(
+        DummyI2c::new_with_tx(&[0x3d]),
+        i2c_interface::Config::default(),
+    );
+    let result = Lsm9ds1Builder::new()
+        .with_identity_check(true)
+        .init_on(interface);
 
-        const BIAS: f32 = 25.0;
-        const SCALE: f32 = 16.0;
+    assert!(
+        matches!(
+            result,
+            Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::AccelGyro,
+                found: 0x3d
+            })
+        ),
+        "Wrong AG identity accepted"
+    );
 
-        let temp = ((temp as f32) / SCALE) + BIAS;
-        Ok(temp)
+    let interface = interface::I2cInterface::new(
+        DummyI2c::new_with_tx(&[DEVICE_ID_AG]),
+        i2c_interface::Config::default(),
+    );
+    let mut device = Lsm9ds1Builder::new()
+        .init_on(interface)
+        .expect("Identity checked without being enabled");
+
+    assert!(
+        matches!(
+            device.verify_identity(),
+            Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::Magnetometer,
+                found: DEVICE_ID_AG
+            })
+        ),
+        "Wrong M identity accepted"
+    );
+}
+
+#[test]
+fn identity_check_three_wire_spi() {
+    use embedded_hal::spi::{ErrorKind, ErrorType, Operation, SpiDevice};
+
+    /// A die on a 3-wire SPI bus, which only drives the shared line once its SIM bit is set.
+    struct ThreeWireDie {
+        sim_register: u8,
+        sim_bit: u8,
+        id: u8,
+        three_wire: bool,
+    }
+
+    impl ErrorType for ThreeWireDie {
+        type Error = ErrorKind;
+    }
+
+    impl SpiDevice for ThreeWireDie {
+        fn transaction(&mut self, ops: &mut [Operation<'_, u8>]) -> Result<(), ErrorKind> {
+            match ops {
+                [Operation::Write([reg, value])] if *reg == self.sim_register => {
+                    self.three_wire = *value & self.sim_bit != 0;
+                }
+                [Operation::Write(_), Operation::Read(buf)] => {
+                    // The line floats high while the die doesn't drive it.
+                    buf.fill(if self.three_wire { self.id } else { 0xff });
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+    }
+
+    let die = |sim_bit, id| ThreeWireDie {
+        sim_register: 0x22,
+        sim_bit,
+        id,
+        three_wire: false,
+    };
+    let interface =
+        interface::SpiInterface::new_three_wire(die(0b1000, DEVICE_ID_AG), die(0b100, DEVICE_ID_M));
+
+    Lsm9ds1Builder::new()
+        .with_identity_check(true)
+        .init_on(interface)
+        .expect("Identity not readable over 3-wire SPI");
+}
+
+#[test]
+fn bus_error_source() {
+    #[derive(Debug)]
+    struct BusFault;
+
+    impl Display for BusFault {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            write!(f, "bus fault")
+        }
     }
+
+    impl Error for BusFault {}
+
+    let error = Lsm9ds1Error::Bus(BusFault);
+    let source = error.source().expect("Bus error has no source");
+    assert!(source.is::<BusFault>(), "Wrong error source");
+    assert_eq!(error.to_string(), "Bus error: BusFault", "Wrong message");
+
+    let error = Lsm9ds1Error::<BusFault>::WrongDeviceId {
+        component: Component::Magnetometer,
+        found: 0x68,
+    };
+    assert!(error.source().is_none(), "Unexpected error source");
+}
+
+#[cfg(test)]
+use crate::tests::faulty_i2c::{FaultyError, FaultyI2c};
+
+#[cfg(test)]
+fn faulty_device(i2c: FaultyI2c) -> Lsm9ds1<interface::I2cInterface<FaultyI2c>> {
+    Lsm9ds1 {
+        interface: interface::I2cInterface::new(i2c, i2c_interface::Config::default()),
+        config: DeviceConfig::default(),
+        calibration: Calibration::default(),
+    }
+}
+
+#[test]
+fn failed_init_keeps_config() {
+    let mut device = faulty_device(FaultyI2c::new_with_tx(&[0x00]).fail_transaction(10));
+
+    assert!(
+        matches!(device.init(), Err(Lsm9ds1Error::Bus(FaultyError::Failed))),
+        "Failed write not reported"
+    );
+    assert_eq!(device.config, DeviceConfig::default(), "Config changed");
+
+    device.init().expect("Error retrying initialization");
+
+    let mut device = faulty_device(FaultyI2c::new_with_tx(&[0x00]).nack_address(0x1e));
+    assert!(
+        matches!(
+            device.init(),
+            Err(Lsm9ds1Error::Bus(FaultyError::NoAcknowledge(0x1e)))
+        ),
+        "Missing magnetometer not reported"
+    );
+    assert_eq!(device.config, DeviceConfig::default(), "Config changed");
+}
+
+#[test]
+fn failed_spi_init_keeps_spi_mode() {
+    use embedded_hal::spi::{ErrorKind, ErrorType, Operation, SpiDevice};
+
+    /// An SPI device on which every transaction fails.
+    struct FailingSpi;
+
+    impl ErrorType for FailingSpi {
+        type Error = ErrorKind;
+    }
+
+    impl SpiDevice for FailingSpi {
+        fn transaction(&mut self, _ops: &mut [Operation<'_, u8>]) -> Result<(), ErrorKind> {
+            Err(ErrorKind::Other)
+        }
+    }
+
+    let mut device = Lsm9ds1 {
+        interface: interface::SpiInterface::new_three_wire(FailingSpi, FailingSpi),
+        config: DeviceConfig::default(),
+        calibration: Calibration::default(),
+    };
+
+    assert!(device.init().is_err(), "Failed write not reported");
+    assert_eq!(device.config, DeviceConfig::default(), "SPI mode changed");
+}
+
+#[test]
+fn failed_setters_keep_config() {
+    use config::accel_gyro::AccelSamplingRate;
+    use config::interrupts::{AxisEvents, EventCombination};
+
+    let init_transactions = DeviceConfig::default().all_registers().len();
+    let mut device =
+        faulty_device(FaultyI2c::new_with_tx(&[0x00]).timeout_after(init_transactions));
+    device.init().expect("Error during initialization");
+    let expected = device.config.clone();
+
+    let events = AxisEvents {
+        x_high: true,
+        ..Default::default()
+    };
+    let results = [
+        device.set_accelerometer_enabled(false),
+        device.set_gyroscope_enabled(false),
+        device.set_magnetometer_enabled(true),
+        device.set_accel_gyro_sampling_rate(AccelGyroSamplingRate::_952Hz),
+        device.set_accel_sampling_rate(AccelSamplingRate::_952Hz),
+        device.set_int1_routing(Int1Routing {
+            fifo_full: true,
+            ..Default::default()
+        }),
+        device.set_int2_routing(Int2Routing {
+            inactivity: true,
+            ..Default::default()
+        }),
+        device.set_interrupt_pin_config(true, InterruptPinMode::OpenDrain),
+        device.set_accel_interrupt(AccelInterruptConfig {
+            events,
+            combination: EventCombination::And,
+            ..Default::default()
+        }),
+        device.set_gyro_interrupt(GyroInterruptConfig {
+            events,
+            ..Default::default()
+        }),
+        device.set_magnetometer_interrupt(MagInterruptConfig {
+            enabled: true,
+            ..Default::default()
+        }),
+        device.set_inactivity(InactivityConfig {
+            duration: 10,
+            ..Default::default()
+        }),
+        device.set_fifo_config(FifoConfig {
+            enabled: true,
+            ..Default::default()
+        }),
+    ];
+
+    for result in results {
+        assert!(
+            matches!(result, Err(Lsm9ds1Error::Bus(FaultyError::Timeout))),
+            "Timeout not reported"
+        );
+    }
+    assert_eq!(device.config, expected, "Config changed by failed writes");
+}
+
+#[test]
+fn partial_writes_keep_config() {
+    use config::interrupts::AxisEvents;
+
+    let init_transactions = DeviceConfig::default().all_registers().len();
+
+    // Fail the last register write of each multi-register setter.
+    let mut device =
+        faulty_device(FaultyI2c::new_with_tx(&[0x00]).fail_transaction(init_transactions + 7));
+    device.init().expect("Error during initialization");
+    let expected = device.config.clone();
+
+    let result = device.set_gyro_interrupt(GyroInterruptConfig {
+        events: AxisEvents {
+            z_low: true,
+            ..Default::default()
+        },
+        threshold_z: 100.0,
+        duration: 5,
+        ..Default::default()
+    });
+    assert!(result.is_err(), "Partial write not reported");
+    assert_eq!(device.config, expected, "Partially written config recorded");
+
+    let mut device =
+        faulty_device(FaultyI2c::new_with_tx(&[0x00]).fail_transaction(init_transactions + 1));
+    device.init().expect("Error during initialization");
+
+    let result = device.set_fifo_config(FifoConfig {
+        enabled: true,
+        mode: config::fifo::FifoMode::Continuous,
+        threshold: 8,
+        stop_on_threshold: false,
+    });
+    assert!(result.is_err(), "Partial write not reported");
+    assert_eq!(device.config, expected, "Partially written config recorded");
+
+    let mut device =
+        faulty_device(FaultyI2c::new_with_tx(&[0x00]).fail_transaction(init_transactions + 5));
+    device.init().expect("Error during initialization");
+
+    let result = device.set_hard_iron_offset(MagneticField {
+        x: 0.1,
+        y: -0.2,
+        z: 0.3,
+    });
+    assert!(result.is_err(), "Partial write not reported");
+    assert_eq!(device.config, expected, "Partially written config recorded");
+}
+
+#[test]
+fn failed_reads_keep_config() {
+    let init_transactions = DeviceConfig::default().all_registers().len();
+    let mut device = faulty_device(
+        FaultyI2c::new_with_tx(&[DEVICE_ID_AG])
+            .corrupt_reads(0x01)
+            .timeout_after(init_transactions + 1),
+    );
+    device.init().expect("Error during initialization");
+    let expected = device.config.clone();
+
+    assert!(
+        matches!(
+            device.verify_identity(),
+            Err(Lsm9ds1Error::WrongDeviceId {
+                component: Component::AccelGyro,
+                found: 0x69
+            })
+        ),
+        "Corrupted identity accepted"
+    );
+
+    assert!(device.read_acceleration().is_err(), "Timeout not reported");
+    assert!(device.read_config().is_err(), "Timeout not reported");
+    assert!(
+        device.self_test(&mut NoDelay).is_err(),
+        "Timeout not reported"
+    );
+    assert!(
+        device.read_fifo(&mut [FifoSample::default(); 4]).is_err(),
+        "Timeout not reported"
+    );
+    assert_eq!(device.config, expected, "Config changed by failed reads");
+}
+
+#[test]
+fn read_fifo_bursts_samples() {
+    let mut device = faulty_device(FaultyI2c::new_with_tx(&[0x03]));
+    let mut buffer = [FifoSample::default(); 4];
+
+    assert_eq!(
+        device.read_fifo(&mut buffer).unwrap(),
+        3,
+        "Wrong sample count"
+    );
+    assert_eq!(
+        device.interface.bus().transactions(),
+        4,
+        "Samples not read in one burst each"
+    );
+
+    device.set_calibration(Calibration {
+        gyro_temperature: Some(calibration::temperature::GyroTemperatureModel::default()),
+        ..Default::default()
+    });
+    assert_eq!(
+        device.read_fifo(&mut buffer).unwrap(),
+        3,
+        "Wrong sample count"
+    );
+    assert_eq!(
+        device.interface.bus().transactions(),
+        4 + 6,
+        "Temperature not read once per batch"
+    );
 }
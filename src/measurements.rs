@@ -0,0 +1,245 @@
+/// Standard gravity in m/s².
+const STANDARD_GRAVITY: f32 = 9.80665;
+/// Microtesla per gauss.
+const MICROTESLA_PER_GAUSS: f32 = 100.0;
+
+/// Temperature offset in °C, corresponding to a raw value of zero.
+const TEMPERATURE_BIAS: f32 = 25.0;
+/// Temperature sensitivity in LSB/°C.
+const TEMPERATURE_SCALE: f32 = 16.0;
+
+/// Raw signed 16-bit sample of a sensor, in LSB.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RawSample {
+    /// X-axis sample.
+    pub x: i16,
+    /// Y-axis sample.
+    pub y: i16,
+    /// Z-axis sample.
+    pub z: i16,
+}
+
+impl RawSample {
+    /// Create a sample from little endian x, y and z values.
+    pub fn from_le_bytes(data: [u8; 6]) -> Self {
+        Self {
+            x: i16::from_le_bytes([data[0], data[1]]),
+            y: i16::from_le_bytes([data[2], data[3]]),
+            z: i16::from_le_bytes([data[4], data[5]]),
+        }
+    }
+
+    /// Scale the sample by a sensitivity, as `[x, y, z]`.
+    fn scale(&self, sensitivity: f32) -> [f32; 3] {
+        [
+            self.x as f32 * sensitivity,
+            self.y as f32 * sensitivity,
+            self.z as f32 * sensitivity,
+        ]
+    }
+}
+
+/// Convert a raw temperature sample to °C.
+pub fn temperature_c_from_raw(raw: i16) -> f32 {
+    (raw as f32) / TEMPERATURE_SCALE + TEMPERATURE_BIAS
+}
+
+/// Angular rate measured by the gyroscope, in degrees per second (dps).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AngularRate {
+    /// Angular rate around the X-axis in dps.
+    pub x: f32,
+    /// Angular rate around the Y-axis in dps.
+    pub y: f32,
+    /// Angular rate around the Z-axis in dps.
+    pub z: f32,
+}
+
+impl AngularRate {
+    /// Convert a raw sample, given the sensitivity in dps/LSB.
+    pub fn from_raw(raw: RawSample, sensitivity: f32) -> Self {
+        let [x, y, z] = raw.scale(sensitivity);
+        Self { x, y, z }
+    }
+
+    /// Angular rate in rad/s.
+    pub fn to_radians_per_second(&self) -> RadiansPerSecond {
+        RadiansPerSecond {
+            x: self.x.to_radians(),
+            y: self.y.to_radians(),
+            z: self.z.to_radians(),
+        }
+    }
+}
+
+/// Angular rate in radians per second (rad/s).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RadiansPerSecond {
+    /// Angular rate around the X-axis in rad/s.
+    pub x: f32,
+    /// Angular rate around the Y-axis in rad/s.
+    pub y: f32,
+    /// Angular rate around the Z-axis in rad/s.
+    pub z: f32,
+}
+
+/// Linear acceleration measured by the accelerometer, in g.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Acceleration {
+    /// Acceleration along the X-axis in g.
+    pub x: f32,
+    /// Acceleration along the Y-axis in g.
+    pub y: f32,
+    /// Acceleration along the Z-axis in g.
+    pub z: f32,
+}
+
+impl Acceleration {
+    /// Convert a raw sample, given the sensitivity in g/LSB.
+    pub fn from_raw(raw: RawSample, sensitivity: f32) -> Self {
+        let [x, y, z] = raw.scale(sensitivity);
+        Self { x, y, z }
+    }
+
+    /// Acceleration in m/s².
+    pub fn to_meters_per_second_squared(&self) -> MetersPerSecondSquared {
+        MetersPerSecondSquared {
+            x: self.x * STANDARD_GRAVITY,
+            y: self.y * STANDARD_GRAVITY,
+            z: self.z * STANDARD_GRAVITY,
+        }
+    }
+}
+
+/// Linear acceleration in meters per second squared (m/s²).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MetersPerSecondSquared {
+    /// Acceleration along the X-axis in m/s².
+    pub x: f32,
+    /// Acceleration along the Y-axis in m/s².
+    pub y: f32,
+    /// Acceleration along the Z-axis in m/s².
+    pub z: f32,
+}
+
+/// Magnetic field measured by the magnetometer, in gauss.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MagneticField {
+    /// Magnetic field along the X-axis in gauss.
+    pub x: f32,
+    /// Magnetic field along the Y-axis in gauss.
+    pub y: f32,
+    /// Magnetic field along the Z-axis in gauss.
+    pub z: f32,
+}
+
+impl MagneticField {
+    /// Convert a raw sample, given the sensitivity in gauss/LSB.
+    pub fn from_raw(raw: RawSample, sensitivity: f32) -> Self {
+        let [x, y, z] = raw.scale(sensitivity);
+        Self { x, y, z }
+    }
+
+    /// Magnetic field in µT.
+    pub fn to_microtesla(&self) -> Microtesla {
+        Microtesla {
+            x: self.x * MICROTESLA_PER_GAUSS,
+            y: self.y * MICROTESLA_PER_GAUSS,
+            z: self.z * MICROTESLA_PER_GAUSS,
+        }
+    }
+}
+
+/// Magnetic field in microtesla (µT).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Microtesla {
+    /// Magnetic field along the X-axis in µT.
+    pub x: f32,
+    /// Magnetic field along the Y-axis in µT.
+    pub y: f32,
+    /// Magnetic field along the Z-axis in µT.
+    pub z: f32,
+}
+
+/// Gyroscope and accelerometer sample read out from the FIFO.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FifoSample {
+    /// Angular rate measured by the gyroscope.
+    pub angular_rate: AngularRate,
+    /// Acceleration measured by the accelerometer.
+    pub acceleration: Acceleration,
+}
+
+#[test]
+fn raw_conversions() {
+    let raw = RawSample::from_le_bytes([0x00, 0x40, 0x00, 0xc0, 0xff, 0xff]);
+    assert_eq!(
+        raw,
+        RawSample {
+            x: 16384,
+            y: -16384,
+            z: -1
+        },
+        "Wrong raw sample"
+    );
+
+    let acceleration = Acceleration::from_raw(raw, 0.5);
+    assert_eq!(
+        acceleration,
+        Acceleration {
+            x: 8192.0,
+            y: -8192.0,
+            z: -0.5
+        },
+        "Wrong scaled sample"
+    );
+
+    assert_eq!(temperature_c_from_raw(0), 25.0, "Wrong temperature");
+    assert_eq!(temperature_c_from_raw(-80), 20.0, "Wrong temperature");
+}
+
+#[test]
+fn unit_conversions() {
+    let rate = AngularRate {
+        x: 180.0,
+        y: -90.0,
+        z: 0.0,
+    };
+    let RadiansPerSecond { x, y, z } = rate.to_radians_per_second();
+    assert!((x - core::f32::consts::PI).abs() < 1e-6, "Wrong X rate");
+    assert!(
+        (y + core::f32::consts::FRAC_PI_2).abs() < 1e-6,
+        "Wrong Y rate"
+    );
+    assert_eq!(z, 0.0, "Wrong Z rate");
+
+    let acceleration = Acceleration {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    assert_eq!(
+        acceleration.to_meters_per_second_squared(),
+        MetersPerSecondSquared {
+            x: 0.0,
+            y: 0.0,
+            z: 9.80665
+        },
+        "Wrong acceleration"
+    );
+
+    let field = MagneticField {
+        x: 0.5,
+        y: -0.25,
+        z: 0.0,
+    };
+    assert_eq!(
+        field.to_microtesla(),
+        Microtesla {
+            x: 50.0,
+            y: -25.0,
+            z: 0.0
+        },
+        "Wrong field"
+    );
+}
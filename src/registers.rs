@@ -3,9 +3,16 @@ use crate::config::{
         AccelBandWidth, AccelDecimation, AccelFullScale, AccelGyroSamplingRate, AccelLowpassCutoff,
         AccelSamplingRate, GyroFullScale,
     },
+    activity::GyroInactivityMode,
+    fifo::{FifoMode, FifoStatus},
+    interrupts::{
+        AxisEvents, CounterMode, EventCombination, Int1Routing, Int2Routing, InterruptSource,
+        MagInterruptSource,
+    },
     magnetometer::{FullScale, OperatingMode, PerformanceMode, SamplingRate},
     Endianness, InterruptPinMode, SPIMode,
 };
+use crate::status::Status;
 
 /// Register address of a sensor component.
 #[derive(Clone, Copy)]
@@ -125,7 +132,7 @@ impl Register {
             Self::OUT_Z_L_G => ComponentAddress::Ag(0x1c),
             Self::OUT_Z_H_G => ComponentAddress::Ag(0x1d),
             Self::CTRL_REG4 => ComponentAddress::Ag(0x1e),
-            Self::CTRL_REG5_XL => ComponentAddress::Ag(0xf1),
+            Self::CTRL_REG5_XL => ComponentAddress::Ag(0x1f),
             Self::CTRL_REG6_XL => ComponentAddress::Ag(0x20),
             Self::CTRL_REG7_XL => ComponentAddress::Ag(0x21),
             Self::CTRL_REG8 => ComponentAddress::Ag(0x22),
@@ -176,6 +183,162 @@ impl Register {
     }
 }
 
+/// Convert an inactivity threshold in g to the ACT_THS register value. Like the interrupt generator
+/// thresholds, the threshold is compared against the high byte of the accelerometer output.
+pub fn act_ths(threshold: f32, sensitivity: f32, gyro_mode: GyroInactivityMode) -> u8 {
+    let gyro_mode = (match gyro_mode {
+        GyroInactivityMode::PowerDown => 0b0,
+        GyroInactivityMode::Sleep => 0b1,
+    }) << 7;
+    let threshold = (threshold / (sensitivity * 256.0)).clamp(0.0, 0b111_1111 as f32) as u8;
+
+    gyro_mode | threshold
+}
+
+pub fn int1_ctrl(routing: Int1Routing) -> u8 {
+    let gyro_interrupt = (if routing.gyro_interrupt { 0b1 } else { 0b0 }) << 7;
+    let accel_interrupt = (if routing.accel_interrupt { 0b1 } else { 0b0 }) << 6;
+    let fifo_full = (if routing.fifo_full { 0b1 } else { 0b0 }) << 5;
+    let fifo_overrun = (if routing.fifo_overrun { 0b1 } else { 0b0 }) << 4;
+    let fifo_threshold = (if routing.fifo_threshold { 0b1 } else { 0b0 }) << 3;
+    let boot_status = (if routing.boot_status { 0b1 } else { 0b0 }) << 2;
+    let gyro_data_ready = (if routing.gyro_data_ready { 0b1 } else { 0b0 }) << 1;
+    let accel_data_ready = if routing.accel_data_ready { 0b1 } else { 0b0 }; // << 0
+
+    gyro_interrupt
+        | accel_interrupt
+        | fifo_full
+        | fifo_overrun
+        | fifo_threshold
+        | boot_status
+        | gyro_data_ready
+        | accel_data_ready
+}
+
+pub fn decode_int1_ctrl(value: u8) -> Int1Routing {
+    Int1Routing {
+        gyro_interrupt: value & (0b1 << 7) != 0,
+        accel_interrupt: value & (0b1 << 6) != 0,
+        fifo_full: value & (0b1 << 5) != 0,
+        fifo_overrun: value & (0b1 << 4) != 0,
+        fifo_threshold: value & (0b1 << 3) != 0,
+        boot_status: value & (0b1 << 2) != 0,
+        gyro_data_ready: value & (0b1 << 1) != 0,
+        accel_data_ready: value & 0b1 != 0,
+    }
+}
+
+pub fn int2_ctrl(routing: Int2Routing) -> u8 {
+    let inactivity = (if routing.inactivity { 0b1 } else { 0b0 }) << 7;
+    let fifo_full = (if routing.fifo_full { 0b1 } else { 0b0 }) << 5;
+    let fifo_overrun = (if routing.fifo_overrun { 0b1 } else { 0b0 }) << 4;
+    let fifo_threshold = (if routing.fifo_threshold { 0b1 } else { 0b0 }) << 3;
+    let temperature_data_ready = (if routing.temperature_data_ready {
+        0b1
+    } else {
+        0b0
+    }) << 2;
+    let gyro_data_ready = (if routing.gyro_data_ready { 0b1 } else { 0b0 }) << 1;
+    let accel_data_ready = if routing.accel_data_ready { 0b1 } else { 0b0 }; // << 0
+
+    inactivity
+        | fifo_full
+        | fifo_overrun
+        | fifo_threshold
+        | temperature_data_ready
+        | gyro_data_ready
+        | accel_data_ready
+}
+
+pub fn decode_int2_ctrl(value: u8) -> Int2Routing {
+    Int2Routing {
+        inactivity: value & (0b1 << 7) != 0,
+        fifo_full: value & (0b1 << 5) != 0,
+        fifo_overrun: value & (0b1 << 4) != 0,
+        fifo_threshold: value & (0b1 << 3) != 0,
+        temperature_data_ready: value & (0b1 << 2) != 0,
+        gyro_data_ready: value & (0b1 << 1) != 0,
+        accel_data_ready: value & 0b1 != 0,
+    }
+}
+
+pub fn int_gen_cfg_xl(combination: EventCombination, events: AxisEvents) -> u8 {
+    let combination = (match combination {
+        EventCombination::Or => 0b0,
+        EventCombination::And => 0b1,
+    }) << 7;
+
+    combination | axis_events(events)
+}
+
+/// Convert a threshold in g to the 8-bit accelerometer threshold, which is compared against the
+/// high byte of the accelerometer output.
+pub fn int_gen_ths_xl(threshold: f32, sensitivity: f32) -> u8 {
+    let threshold = threshold / (sensitivity * 256.0);
+    threshold.clamp(0.0, u8::MAX as f32) as u8
+}
+
+pub fn int_gen_dur(wait: bool, duration: u8) -> u8 {
+    let wait = (if wait { 0b1 } else { 0b0 }) << 7;
+    let duration = duration.min(0b111_1111); // << 0
+
+    wait | duration
+}
+
+pub fn int_gen_cfg_g(combination: EventCombination, latched: bool, events: AxisEvents) -> u8 {
+    let combination = (match combination {
+        EventCombination::Or => 0b0,
+        EventCombination::And => 0b1,
+    }) << 7;
+    let latched = (if latched { 0b1 } else { 0b0 }) << 6;
+
+    combination | latched | axis_events(events)
+}
+
+/// Convert a threshold in dps to the 15-bit gyroscope threshold, as high and low byte.
+pub fn int_gen_ths_g(threshold: f32, sensitivity: f32) -> (u8, u8) {
+    let threshold = (threshold / sensitivity).clamp(0.0, 0x7fff as f32) as u16;
+    let [high, low] = threshold.to_be_bytes();
+
+    (high, low)
+}
+
+pub fn int_gen_ths_xh_g(counter_mode: CounterMode, threshold_high: u8) -> u8 {
+    let counter_mode = (match counter_mode {
+        CounterMode::Reset => 0b0,
+        CounterMode::Decrement => 0b1,
+    }) << 7;
+
+    counter_mode | threshold_high
+}
+
+/// Encode per-axis events, as used by the interrupt generator configuration registers.
+fn axis_events(events: AxisEvents) -> u8 {
+    let z_high = (if events.z_high { 0b1 } else { 0b0 }) << 5;
+    let z_low = (if events.z_low { 0b1 } else { 0b0 }) << 4;
+    let y_high = (if events.y_high { 0b1 } else { 0b0 }) << 3;
+    let y_low = (if events.y_low { 0b1 } else { 0b0 }) << 2;
+    let x_high = (if events.x_high { 0b1 } else { 0b0 }) << 1;
+    let x_low = if events.x_low { 0b1 } else { 0b0 }; // << 0
+
+    z_high | z_low | y_high | y_low | x_high | x_low
+}
+
+/// Decode an interrupt generator source register (INT_GEN_SRC_XL or INT_GEN_SRC_G).
+pub fn int_gen_src(value: u8) -> InterruptSource {
+    InterruptSource {
+        active: value & (0b1 << 6) != 0,
+        events: AxisEvents {
+            z_high: value & (0b1 << 5) != 0,
+            z_low: value & (0b1 << 4) != 0,
+            y_high: value & (0b1 << 3) != 0,
+            y_low: value & (0b1 << 2) != 0,
+            x_high: value & (0b1 << 1) != 0,
+            x_low: value & 0b1 != 0,
+        },
+    }
+}
+
 pub fn ctrl_reg_1_g(
     accel_gyro_sampling_rate: AccelGyroSamplingRate,
     gyro_full_scale: GyroFullScale,
@@ -201,6 +364,30 @@ pub fn ctrl_reg_1_g(
     gyro_sampling_rate | gyro_full_scale | gyro_bandwidth
 }
 
+/// Decode CTRL_REG1_G as sampling rate and gyroscope full scale. Returns `None` for reserved
+/// values.
+pub fn decode_ctrl_reg_1_g(value: u8) -> Option<(AccelGyroSamplingRate, GyroFullScale)> {
+    let sampling_rate = match value >> 5 {
+        0b000 => AccelGyroSamplingRate::PowerDown,
+        0b001 => AccelGyroSamplingRate::_14p9Hz,
+        0b010 => AccelGyroSamplingRate::_59p5Hz,
+        0b011 => AccelGyroSamplingRate::_119Hz,
+        0b100 => AccelGyroSamplingRate::_238Hz,
+        0b101 => AccelGyroSamplingRate::_476Hz,
+        0b110 => AccelGyroSamplingRate::_952Hz,
+        _ => return None,
+    };
+
+    let full_scale = match (value >> 3) & 0b11 {
+        0b00 => GyroFullScale::_245dps,
+        0b01 => GyroFullScale::_500dps,
+        0b11 => GyroFullScale::_2000dps,
+        _ => return None,
+    };
+
+    Some((sampling_rate, full_scale))
+}
+
 pub fn ctrl_reg_2_g(gyro_high_pass_filter: bool, gyro_low_pass_filter: bool) -> u8 {
     match (gyro_high_pass_filter, gyro_low_pass_filter) {
         (false, false) => 0b00,
@@ -209,6 +396,12 @@ pub fn ctrl_reg_2_g(gyro_high_pass_filter: bool, gyro_low_pass_filter: bool) ->
     }
 }
 
+/// Decode CTRL_REG2_G as the gyroscope low pass filter flag. The high pass filter flag is
+/// decoded from CTRL_REG3_G.
+pub fn decode_ctrl_reg_2_g(value: u8) -> bool {
+    value & 0b10 != 0
+}
+
 pub fn ctrl_reg_3_g(gyro_low_power_mode: bool, gyro_high_pass_filter: bool) -> u8 {
     let gyro_high_pass_enable = (if gyro_high_pass_filter { 0b1 } else { 0b0 }) << 6;
     let gyro_low_power = (if gyro_low_power_mode { 0b1 } else { 0b0 }) << 7;
@@ -217,16 +410,41 @@ pub fn ctrl_reg_3_g(gyro_low_power_mode: bool, gyro_high_pass_filter: bool) -> u
     gyro_low_power | gyro_high_pass_enable | gyro_hpf_cutoff
 }
 
+/// Decode CTRL_REG3_G as low power mode and high pass filter flags.
+pub fn decode_ctrl_reg_3_g(value: u8) -> (bool, bool) {
+    let gyro_low_power_mode = value & (0b1 << 7) != 0;
+    let gyro_high_pass_filter = value & (0b1 << 6) != 0;
+
+    (gyro_low_power_mode, gyro_high_pass_filter)
+}
+
 pub fn ctrl_reg_4(
     gyro_x_axis_enabled: bool,
     gyro_y_axis_enabled: bool,
     gyro_z_axis_enabled: bool,
+    accel_interrupt_latched: bool,
 ) -> u8 {
     let gyro_x_en = (if gyro_x_axis_enabled { 0b1 } else { 0b0 }) << 3;
     let gyro_y_en = (if gyro_y_axis_enabled { 0b1 } else { 0b0 }) << 4;
     let gyro_z_en = (if gyro_z_axis_enabled { 0b1 } else { 0b0 }) << 5;
+    let accel_interrupt_latched = (if accel_interrupt_latched { 0b1 } else { 0b0 }) << 1;
+
+    gyro_x_en | gyro_y_en | gyro_z_en | accel_interrupt_latched
+}
 
-    gyro_x_en | gyro_y_en | gyro_z_en
+/// Decode CTRL_REG4 as gyroscope X, Y, Z axis enable and accelerometer interrupt latch flags.
+pub fn decode_ctrl_reg_4(value: u8) -> (bool, bool, bool, bool) {
+    let gyro_x_axis_enabled = value & (0b1 << 3) != 0;
+    let gyro_y_axis_enabled = value & (0b1 << 4) != 0;
+    let gyro_z_axis_enabled = value & (0b1 << 5) != 0;
+    let accel_interrupt_latched = value & (0b1 << 1) != 0;
+
+    (
+        gyro_x_axis_enabled,
+        gyro_y_axis_enabled,
+        gyro_z_axis_enabled,
+        accel_interrupt_latched,
+    )
 }
 
 pub fn ctrl_reg_5_xl(
@@ -244,11 +462,32 @@ pub fn ctrl_reg_5_xl(
 
     let accel_x_en = (if accel_x_axis_enabled { 0b1 } else { 0b0 }) << 3;
     let accel_y_en = (if accel_y_axis_enabled { 0b1 } else { 0b0 }) << 4;
-    let accel_z_en = (if accel_z_axis_enabled { 0b0 } else { 0b1 }) << 5;
+    let accel_z_en = (if accel_z_axis_enabled { 0b1 } else { 0b0 }) << 5;
 
     accel_dec | accel_x_en | accel_y_en | accel_z_en
 }
 
+/// Decode CTRL_REG5_XL as decimation and accelerometer X, Y, Z axis enable flags.
+pub fn decode_ctrl_reg_5_xl(value: u8) -> (AccelDecimation, bool, bool, bool) {
+    let accel_decimation = match value >> 6 {
+        0b00 => AccelDecimation::None,
+        0b01 => AccelDecimation::Half,
+        0b10 => AccelDecimation::Quarter,
+        _ => AccelDecimation::Eighth,
+    };
+
+    let accel_x_axis_enabled = value & (0b1 << 3) != 0;
+    let accel_y_axis_enabled = value & (0b1 << 4) != 0;
+    let accel_z_axis_enabled = value & (0b1 << 5) != 0;
+
+    (
+        accel_decimation,
+        accel_x_axis_enabled,
+        accel_y_axis_enabled,
+        accel_z_axis_enabled,
+    )
+}
+
 pub fn ctrl_reg_6_xl(
     accel_only_sampling_rate: AccelSamplingRate,
     accel_full_scale: AccelFullScale,
@@ -282,6 +521,41 @@ pub fn ctrl_reg_6_xl(
     accel_sampling_rate | accel_full_scale | accel_bandwidth
 }
 
+/// Decode CTRL_REG6_XL as accelerometer sampling rate, full scale and bandwidth. Returns `None`
+/// for reserved values.
+pub fn decode_ctrl_reg_6_xl(
+    value: u8,
+) -> Option<(AccelSamplingRate, AccelFullScale, AccelBandWidth)> {
+    let sampling_rate = match value >> 5 {
+        0b000 => AccelSamplingRate::PowerDown,
+        0b001 => AccelSamplingRate::_10Hz,
+        0b010 => AccelSamplingRate::_50Hz,
+        0b011 => AccelSamplingRate::_119Hz,
+        0b100 => AccelSamplingRate::_238Hz,
+        0b101 => AccelSamplingRate::_476Hz,
+        0b110 => AccelSamplingRate::_952Hz,
+        _ => return None,
+    };
+
+    let full_scale = match (value >> 3) & 0b11 {
+        0b00 => AccelFullScale::PlusMinus2g,
+        0b10 => AccelFullScale::PlusMinus4g,
+        0b11 => AccelFullScale::PlutMinus8g,
+        _ => AccelFullScale::PlusMinus16g,
+    };
+
+    // The bandwidth selection bits are ignored unless BW_SCAL_ODR is set.
+    let bandwidth = match value & 0b111 {
+        0b100 => AccelBandWidth::_408Hz,
+        0b101 => AccelBandWidth::_211Hz,
+        0b110 => AccelBandWidth::_105Hz,
+        0b111 => AccelBandWidth::_50Hz,
+        _ => AccelBandWidth::Auto,
+    };
+
+    Some((sampling_rate, full_scale, bandwidth))
+}
+
 pub fn ctrl_reg_7_xl(
     accel_high_resolution: bool,
     accel_low_pass_cutoff: AccelLowpassCutoff,
@@ -301,6 +575,27 @@ pub fn ctrl_reg_7_xl(
     accel_high_res | accel_low_pass_cutoff | accel_filter_enabled
 }
 
+/// Decode CTRL_REG7_XL as high resolution flag, low pass cutoff and filter enable flag.
+pub fn decode_ctrl_reg_7_xl(value: u8) -> (bool, AccelLowpassCutoff, bool) {
+    let accel_high_resolution = value & (0b1 << 7) != 0;
+
+    let accel_low_pass_cutoff = match (value >> 5) & 0b11 {
+        0b10 => AccelLowpassCutoff::_ODRby9,
+        0b00 => AccelLowpassCutoff::_ODRBy50,
+        0b01 => AccelLowpassCutoff::_ODRBy100,
+        _ => AccelLowpassCutoff::_ODRBy400,
+    };
+
+    let accel_filter_enabled = value & (0b1 << 2) != 0;
+
+    (
+        accel_high_resolution,
+        accel_low_pass_cutoff,
+        accel_filter_enabled,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn ctrl_reg8(
     reboot_memory_content: bool,
     block_data_update: bool,
@@ -342,6 +637,128 @@ pub fn ctrl_reg8(
         | reset
 }
 
+/// Decode CTRL_REG8 as reboot, block data update, interrupt active low, interrupt pin mode, SPI
+/// mode, register increment and endianness. The self-clearing software reset bit is ignored.
+pub fn decode_ctrl_reg8(
+    value: u8,
+) -> (
+    bool,
+    bool,
+    bool,
+    InterruptPinMode,
+    SPIMode,
+    bool,
+    Endianness,
+) {
+    let reboot_memory_content = value & (0b1 << 7) != 0;
+    let block_data_update = value & (0b1 << 6) != 0;
+    let interrupt_active_low = value & (0b1 << 5) != 0;
+    let interrupt_pin_mode = if value & (0b1 << 4) != 0 {
+        InterruptPinMode::OpenDrain
+    } else {
+        InterruptPinMode::PushPull
+    };
+    let spi_mode = if value & (0b1 << 3) != 0 {
+        SPIMode::ThreeWire
+    } else {
+        SPIMode::FourWire
+    };
+    let register_increment = value & (0b1 << 2) != 0;
+    let ag_endianness = if value & (0b1 << 1) != 0 {
+        Endianness::LittleEndian
+    } else {
+        Endianness::BigEndian
+    };
+
+    (
+        reboot_memory_content,
+        block_data_update,
+        interrupt_active_low,
+        interrupt_pin_mode,
+        spi_mode,
+        register_increment,
+        ag_endianness,
+    )
+}
+
+pub fn ctrl_reg_9(fifo_enabled: bool, stop_on_threshold: bool) -> u8 {
+    let fifo_enabled = (if fifo_enabled { 0b1 } else { 0b0 }) << 1;
+    let stop_on_threshold = if stop_on_threshold { 0b1 } else { 0b0 }; // << 0
+
+    fifo_enabled | stop_on_threshold
+}
+
+/// Decode CTRL_REG9 as FIFO enable and stop on threshold flags.
+pub fn decode_ctrl_reg_9(value: u8) -> (bool, bool) {
+    let fifo_enabled = value & (0b1 << 1) != 0;
+    let stop_on_threshold = value & 0b1 != 0;
+
+    (fifo_enabled, stop_on_threshold)
+}
+
+pub fn ctrl_reg_10(gyro_self_test: bool, accel_self_test: bool) -> u8 {
+    let gyro_self_test = (if gyro_self_test { 0b1 } else { 0b0 }) << 2;
+    let accel_self_test = if accel_self_test { 0b1 } else { 0b0 }; // << 0
+
+    gyro_self_test | accel_self_test
+}
+
+/// Decode CTRL_REG10 as gyroscope and accelerometer self-test flags.
+pub fn decode_ctrl_reg_10(value: u8) -> (bool, bool) {
+    let gyro_self_test = value & (0b1 << 2) != 0;
+    let accel_self_test = value & 0b1 != 0;
+
+    (gyro_self_test, accel_self_test)
+}
+
+pub fn fifo_ctrl(fifo_mode: FifoMode, fifo_threshold: u8) -> u8 {
+    let fifo_mode = (match fifo_mode {
+        FifoMode::Bypass => 0b000,
+        FifoMode::Fifo => 0b001,
+        FifoMode::ContinuousToFifo => 0b011,
+        FifoMode::BypassToContinuous => 0b100,
+        FifoMode::Continuous => 0b110,
+    }) << 5;
+    let fifo_threshold = fifo_threshold.min(0b1_1111); // << 0
+
+    fifo_mode | fifo_threshold
+}
+
+/// Decode FIFO_CTRL as FIFO mode and threshold. Returns `None` for reserved modes.
+pub fn decode_fifo_ctrl(value: u8) -> Option<(FifoMode, u8)> {
+    let fifo_mode = match value >> 5 {
+        0b000 => FifoMode::Bypass,
+        0b001 => FifoMode::Fifo,
+        0b011 => FifoMode::ContinuousToFifo,
+        0b100 => FifoMode::BypassToContinuous,
+        0b110 => FifoMode::Continuous,
+        _ => return None,
+    };
+    let fifo_threshold = value & 0b1_1111;
+
+    Some((fifo_mode, fifo_threshold))
+}
+
+pub fn fifo_src(value: u8) -> FifoStatus {
+    FifoStatus {
+        level: value & 0b11_1111,
+        overrun: value & (0b1 << 6) != 0,
+        threshold_reached: value & (0b1 << 7) != 0,
+    }
+}
+
+pub fn status_reg(value: u8) -> Status {
+    Status {
+        accel_interrupt: value & (0b1 << 6) != 0,
+        gyro_interrupt: value & (0b1 << 5) != 0,
+        inactive: value & (0b1 << 4) != 0,
+        boot_running: value & (0b1 << 3) != 0,
+        temperature_data_available: value & (0b1 << 2) != 0,
+        gyro_data_available: value & (0b1 << 1) != 0,
+        accel_data_available: value & 0b1 != 0,
+    }
+}
+
 pub fn ctrl_reg_1_m(
     temperature_compensation: bool,
     xy_performance_mode: PerformanceMode,
@@ -372,6 +789,43 @@ pub fn ctrl_reg_1_m(
     temperature_compensation | xy_performance_mode | sampling_rate | fast_odr | self_test
 }
 
+/// Decode CTRL_REG1_M as temperature compensation, XY performance mode, sampling rate, fast
+/// sampling and self test flags.
+pub fn decode_ctrl_reg_1_m(value: u8) -> (bool, PerformanceMode, SamplingRate, bool, bool) {
+    let temperature_compensation = value & (1 << 7) != 0;
+    let xy_performance_mode = performance_mode(value >> 5);
+    let sampling_rate = match (value >> 2) & 0b111 {
+        0b000 => SamplingRate::_0p625Hz,
+        0b001 => SamplingRate::_1p25Hz,
+        0b010 => SamplingRate::_2p5Hz,
+        0b011 => SamplingRate::_5Hz,
+        0b100 => SamplingRate::_10Hz,
+        0b101 => SamplingRate::_20Hz,
+        0b110 => SamplingRate::_40Hz,
+        _ => SamplingRate::_80Hz,
+    };
+    let fast_sampling = value & (1 << 1) != 0;
+    let self_test = value & 1 != 0;
+
+    (
+        temperature_compensation,
+        xy_performance_mode,
+        sampling_rate,
+        fast_sampling,
+        self_test,
+    )
+}
+
+/// Decode a 2-bit magnetometer performance mode field.
+fn performance_mode(bits: u8) -> PerformanceMode {
+    match bits & 0b11 {
+        0b00 => PerformanceMode::LowPower,
+        0b01 => PerformanceMode::MediumPerformance,
+        0b10 => PerformanceMode::HighPerformance,
+        _ => PerformanceMode::UtraHighPerformance,
+    }
+}
+
 pub fn ctrl_reg_2_m(full_scale: FullScale) -> u8 {
     (match full_scale {
         FullScale::PlusMinus4Gauss => 0b00,
@@ -381,6 +835,15 @@ pub fn ctrl_reg_2_m(full_scale: FullScale) -> u8 {
     }) << 5
 }
 
+pub fn decode_ctrl_reg_2_m(value: u8) -> FullScale {
+    match (value >> 5) & 0b11 {
+        0b00 => FullScale::PlusMinus4Gauss,
+        0b01 => FullScale::PlusMinus8Gauss,
+        0b10 => FullScale::PlusMinus12Gauss,
+        _ => FullScale::PlusMinus16Gauss,
+    }
+}
+
 pub fn ctrl_rg_3_m(
     i2c_disabled: bool,
     low_power_mode: bool,
@@ -399,6 +862,21 @@ pub fn ctrl_rg_3_m(
     i2c_disabled | low_power_mode | spi_mode | operating_mode
 }
 
+/// Decode CTRL_REG3_M as I²C disable, low power mode and SPI write only flags, and operating mode.
+pub fn decode_ctrl_reg_3_m(value: u8) -> (bool, bool, bool, OperatingMode) {
+    let i2c_disabled = value & (1 << 7) != 0;
+    let low_power_mode = value & (1 << 5) != 0;
+    let spi_write_only = value & (1 << 2) == 0;
+    // Both 0b10 and 0b11 select power-down mode.
+    let operating_mode = match value & 0b11 {
+        0b00 => OperatingMode::ContinuousConversion,
+        0b01 => OperatingMode::SingleConversion,
+        _ => OperatingMode::PowerDown,
+    };
+
+    (i2c_disabled, low_power_mode, spi_write_only, operating_mode)
+}
+
 pub fn ctrl_reg_4_m(z_performance_mode: PerformanceMode, data_endianness: Endianness) -> u8 {
     let z_performance_mode = (match z_performance_mode {
         PerformanceMode::LowPower => 0b00,
@@ -414,9 +892,119 @@ pub fn ctrl_reg_4_m(z_performance_mode: PerformanceMode, data_endianness: Endian
     z_performance_mode | endianness
 }
 
+/// Decode CTRL_REG4_M as Z performance mode and data endianness.
+pub fn decode_ctrl_reg_4_m(value: u8) -> (PerformanceMode, Endianness) {
+    let z_performance_mode = performance_mode(value >> 2);
+    let endianness = if value & (1 << 1) != 0 {
+        Endianness::LittleEndian
+    } else {
+        Endianness::BigEndian
+    };
+
+    (z_performance_mode, endianness)
+}
+
 pub fn ctrl_reg_5_m(fast_read: bool, block_data_update: bool) -> u8 {
     let fast_read = (if fast_read { 1 } else { 0 }) << 7;
     let block_data_update = (if block_data_update { 1 } else { 0 }) << 6;
 
     fast_read | block_data_update
 }
+
+/// Decode CTRL_REG5_M as fast read and block data update flags.
+pub fn decode_ctrl_reg_5_m(value: u8) -> (bool, bool) {
+    let fast_read = value & (1 << 7) != 0;
+    let block_data_update = value & (1 << 6) != 0;
+
+    (fast_read, block_data_update)
+}
+
+pub fn int_cfg_m(
+    x_axis_enabled: bool,
+    y_axis_enabled: bool,
+    z_axis_enabled: bool,
+    active_high: bool,
+    latched: bool,
+    enabled: bool,
+) -> u8 {
+    let x_axis_enabled = (if x_axis_enabled { 1 } else { 0 }) << 7;
+    let y_axis_enabled = (if y_axis_enabled { 1 } else { 0 }) << 6;
+    let z_axis_enabled = (if z_axis_enabled { 1 } else { 0 }) << 5;
+    let reserved = 1 << 3; // Must be set for correct operation.
+    let active_high = (if active_high { 1 } else { 0 }) << 2;
+    let not_latched = (if latched { 0 } else { 1 }) << 1;
+    let enabled = if enabled { 1 } else { 0 }; // << 0
+
+    x_axis_enabled
+        | y_axis_enabled
+        | z_axis_enabled
+        | reserved
+        | active_high
+        | not_latched
+        | enabled
+}
+
+/// Convert a hard-iron offset in gauss to the 16-bit magnetometer offset, rounded to the nearest
+/// LSB, as low and high byte.
+pub fn offset_m(offset: f32, sensitivity: f32) -> (u8, u8) {
+    let offset = offset / sensitivity;
+    let offset = if offset < 0.0 {
+        offset - 0.5
+    } else {
+        offset + 0.5
+    };
+    let offset = offset.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+    let [low, high] = offset.to_le_bytes();
+
+    (low, high)
+}
+
+/// Convert the low and high byte of a 16-bit magnetometer offset to gauss.
+pub fn decode_offset_m(low: u8, high: u8, sensitivity: f32) -> f32 {
+    i16::from_le_bytes([low, high]) as f32 * sensitivity
+}
+
+/// Convert a threshold in gauss to the 15-bit magnetometer threshold, as low and high byte.
+pub fn int_ths_m(threshold: f32, sensitivity: f32) -> (u8, u8) {
+    let threshold = (threshold / sensitivity).clamp(0.0, 0x7fff as f32) as u16;
+    let [low, high] = threshold.to_le_bytes();
+
+    (low, high)
+}
+
+pub fn int_src_m(value: u8) -> MagInterruptSource {
+    MagInterruptSource {
+        positive_x: value & (1 << 7) != 0,
+        positive_y: value & (1 << 6) != 0,
+        positive_z: value & (1 << 5) != 0,
+        negative_x: value & (1 << 4) != 0,
+        negative_y: value & (1 << 3) != 0,
+        negative_z: value & (1 << 2) != 0,
+        overflow: value & (1 << 1) != 0,
+        active: value & 1 != 0,
+    }
+}
+
+#[test]
+fn ctrl_reg_5_xl_address_and_axes() {
+    assert!(
+        matches!(Register::CTRL_REG5_XL.addr(), ComponentAddress::Ag(0x1f)),
+        "Wrong CTRL_REG5_XL address"
+    );
+
+    assert_eq!(
+        ctrl_reg_5_xl(AccelDecimation::None, false, false, true),
+        0b0010_0000,
+        "Wrong Z axis enable bit"
+    );
+    assert_eq!(
+        ctrl_reg_5_xl(AccelDecimation::None, true, true, false),
+        0b0001_1000,
+        "Z axis enabled"
+    );
+    assert_eq!(
+        decode_ctrl_reg_5_xl(0b0010_0000),
+        (AccelDecimation::None, false, false, true),
+        "Wrong Z axis decoded"
+    );
+}
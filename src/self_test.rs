@@ -0,0 +1,140 @@
+/// Number of samples averaged for each step of a self-test.
+pub const SELF_TEST_SAMPLES: u8 = 5;
+
+/// Accelerometer self-test output change limits in g, as specified in the datasheet.
+pub const ACCEL_SELF_TEST_LIMITS: SelfTestLimits = SelfTestLimits {
+    min: 0.06,
+    max: 1.7,
+};
+
+/// Gyroscope self-test output change limits in dps, as specified in the datasheet.
+pub const GYRO_SELF_TEST_LIMITS: SelfTestLimits = SelfTestLimits {
+    min: 20.0,
+    max: 250.0,
+};
+
+/// Magnetometer self-test output change limits in gauss for the X, Y and Z axis, as specified in
+/// the datasheet for the ±12 gauss full scale.
+pub const MAG_SELF_TEST_LIMITS: [SelfTestLimits; 3] = [
+    SelfTestLimits { min: 1.0, max: 3.0 },
+    SelfTestLimits { min: 1.0, max: 3.0 },
+    SelfTestLimits { min: 0.1, max: 1.0 },
+];
+
+/// Allowed range of the absolute output change caused by enabling self-test.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SelfTestLimits {
+    /// Minimum output change.
+    pub min: f32,
+    /// Maximum output change.
+    pub max: f32,
+}
+
+/// Self-test result of a single axis.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AxisSelfTest {
+    /// Average output with self-test disabled.
+    pub baseline: f32,
+    /// Average output with self-test enabled.
+    pub self_test: f32,
+    /// Absolute output change caused by the self-test.
+    pub delta: f32,
+    /// The output change is within the limits.
+    pub passed: bool,
+}
+
+impl AxisSelfTest {
+    /// Evaluate the output change of an axis against the limits.
+    pub fn new(baseline: f32, self_test: f32, limits: SelfTestLimits) -> Self {
+        let delta = (self_test - baseline).abs();
+
+        Self {
+            baseline,
+            self_test,
+            delta,
+            passed: delta >= limits.min && delta <= limits.max,
+        }
+    }
+}
+
+/// Self-test result of a sensor.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SensorSelfTest {
+    /// X-axis result.
+    pub x: AxisSelfTest,
+    /// Y-axis result.
+    pub y: AxisSelfTest,
+    /// Z-axis result.
+    pub z: AxisSelfTest,
+}
+
+impl SensorSelfTest {
+    /// Evaluate the `[x, y, z]` averages with self-test disabled and enabled against the limits.
+    pub fn new(baseline: [f32; 3], self_test: [f32; 3], limits: SelfTestLimits) -> Self {
+        Self::with_axis_limits(baseline, self_test, [limits; 3])
+    }
+
+    /// Evaluate the `[x, y, z]` averages with self-test disabled and enabled against separate
+    /// limits for each axis.
+    pub fn with_axis_limits(
+        baseline: [f32; 3],
+        self_test: [f32; 3],
+        limits: [SelfTestLimits; 3],
+    ) -> Self {
+        Self {
+            x: AxisSelfTest::new(baseline[0], self_test[0], limits[0]),
+            y: AxisSelfTest::new(baseline[1], self_test[1], limits[1]),
+            z: AxisSelfTest::new(baseline[2], self_test[2], limits[2]),
+        }
+    }
+
+    /// All axes passed the self-test.
+    pub fn passed(&self) -> bool {
+        self.x.passed && self.y.passed && self.z.passed
+    }
+}
+
+/// Result of the accelerometer and gyroscope self-test.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SelfTestReport {
+    /// Accelerometer result, in g.
+    pub accelerometer: SensorSelfTest,
+    /// Gyroscope result, in dps.
+    pub gyroscope: SensorSelfTest,
+}
+
+impl SelfTestReport {
+    /// Both sensors passed the self-test.
+    pub fn passed(&self) -> bool {
+        self.accelerometer.passed() && self.gyroscope.passed()
+    }
+}
+
+#[test]
+fn axis_limits() {
+    let result = SensorSelfTest::new([0.0, 0.0, 1.0], [0.5, -0.05, -0.5], ACCEL_SELF_TEST_LIMITS);
+
+    assert!(result.x.passed, "X-axis should pass");
+    assert!(!result.y.passed, "Y-axis change below minimum should fail");
+    assert!(
+        (result.z.delta - 1.5).abs() < 1e-6 && result.z.passed,
+        "Z-axis should pass with a negative change"
+    );
+    assert!(!result.passed(), "Sensor should fail if any axis fails");
+
+    let result = SensorSelfTest::new([0.0; 3], [300.0, 100.0, 100.0], GYRO_SELF_TEST_LIMITS);
+    assert!(!result.x.passed, "X-axis change above maximum should fail");
+}
+
+#[test]
+fn magnetometer_axis_limits() {
+    let result =
+        SensorSelfTest::with_axis_limits([0.1, 0.2, 0.3], [1.6, -1.4, 0.8], MAG_SELF_TEST_LIMITS);
+    assert!(result.passed(), "All axes should pass");
+
+    let result = SensorSelfTest::with_axis_limits([0.0; 3], [1.5; 3], MAG_SELF_TEST_LIMITS);
+    assert!(
+        result.x.passed && result.y.passed && !result.z.passed,
+        "Z-axis limits should apply to Z only"
+    );
+}
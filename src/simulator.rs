@@ -0,0 +1,894 @@
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use heapless::Deque;
+
+use crate::interface::i2c::{Address, AddressAg, AddressM};
+use crate::measurements::RawSample;
+use crate::registers::{ComponentAddress, Register};
+use crate::{Component, DEVICE_ID_AG, DEVICE_ID_M};
+
+/// Number of samples that can be queued per sensor.
+pub const SAMPLE_QUEUE_DEPTH: usize = 64;
+
+/// Number of registers in the address space of each die.
+const REGISTER_COUNT: usize = 0x40;
+
+/// Register addresses of the accelerometer/gyroscope die that can be written.
+const WRITABLE_AG: [(u8, u8); 5] = [
+    (0x04, 0x0d), // ACT_THS..INT2_CTRL
+    (0x10, 0x13), // CTRL_REG1_G..ORIENT_CFG_G
+    (0x1e, 0x24), // CTRL_REG4..CTRL_REG10
+    (0x2e, 0x2e), // FIFO_CTRL
+    (0x30, 0x37), // INT_GEN_CFG_G..INT_GEN_DUR_G
+];
+
+/// Register addresses of the magnetometer die that can be written.
+const WRITABLE_M: [(u8, u8); 4] = [
+    (0x05, 0x0a), // OFFSET_X_REG_L_M..OFFSET_Z_REG_H_M
+    (0x20, 0x24), // CTRL_REG1_M..CTRL_REG5_M
+    (0x30, 0x30), // INT_CFG_M
+    (0x32, 0x33), // INT_THS_L_M..INT_THS_H_M
+];
+
+/// Errors reported by the simulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatorError {
+    /// No die responds to the given I2C address.
+    NoAcknowledge(u8),
+}
+
+impl i2c::Error for SimulatorError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NoAcknowledge(_) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        }
+    }
+}
+
+impl core::fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::NoAcknowledge(address) => write!(f, "No acknowledge from {:#04x}", address),
+        }
+    }
+}
+
+impl core::error::Error for SimulatorError {}
+
+/// Register file and address pointer of one die.
+struct Die {
+    registers: [u8; REGISTER_COUNT],
+    pointer: u8,
+    auto_increment: bool,
+}
+
+impl Die {
+    fn new(defaults: &[(Register, u8)]) -> Self {
+        let mut die = Self {
+            registers: [0; REGISTER_COUNT],
+            pointer: 0,
+            auto_increment: false,
+        };
+        die.load_defaults(defaults);
+        die
+    }
+
+    fn load_defaults(&mut self, defaults: &[(Register, u8)]) {
+        self.registers = [0; REGISTER_COUNT];
+        for (reg, value) in defaults {
+            let (ComponentAddress::Ag(addr) | ComponentAddress::M(addr)) = reg.addr();
+            self.registers[addr as usize] = *value;
+        }
+    }
+
+    fn get(&self, reg: Register) -> u8 {
+        let (ComponentAddress::Ag(addr) | ComponentAddress::M(addr)) = reg.addr();
+        self.registers[addr as usize]
+    }
+}
+
+/// Power-on values of the accelerometer/gyroscope registers that are not zero.
+const DEFAULTS_AG: [(Register, u8); 4] = [
+    (Register::WHO_AM_I, DEVICE_ID_AG),
+    (Register::CTRL_REG4, 0x38),
+    (Register::CTRL_REG5_XL, 0x38),
+    (Register::CTRL_REG8, 0x04),
+];
+
+/// Power-on values of the magnetometer registers that are not zero.
+const DEFAULTS_M: [(Register, u8); 4] = [
+    (Register::WHO_AM_I_M, DEVICE_ID_M),
+    (Register::CTRL_REG1_M, 0x10),
+    (Register::CTRL_REG3_M, 0x03),
+    (Register::INT_CFG_M, 0x08),
+];
+
+/// Queued samples and the last sample read out of a sensor.
+struct SampleStream {
+    queue: Deque<RawSample, SAMPLE_QUEUE_DEPTH>,
+    current: RawSample,
+}
+
+impl SampleStream {
+    fn new() -> Self {
+        Self {
+            queue: Deque::new(),
+            current: RawSample::default(),
+        }
+    }
+
+    /// Sample presented in the output registers.
+    fn output(&self) -> RawSample {
+        self.queue.front().copied().unwrap_or(self.current)
+    }
+
+    /// Output register byte at `offset` from OUT_X_L, with `bias` subtracted from the sample.
+    fn output_byte(&self, offset: u8, big_endian: bool, bias: [i16; 3]) -> u8 {
+        let sample = self.output();
+        let value = match offset / 2 {
+            0 => sample.x,
+            1 => sample.y,
+            _ => sample.z,
+        }
+        .saturating_sub(bias[offset as usize / 2]);
+        let bytes = if big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+
+        bytes[(offset % 2) as usize]
+    }
+
+    /// Advance to the next queued sample, once all output registers have been read.
+    fn advance(&mut self) {
+        if let Some(sample) = self.queue.pop_front() {
+            self.current = sample;
+        }
+    }
+}
+
+/// A register-accurate simulation of an LSM9DS1, connected over I²C.
+///
+/// Both dies are modelled with their register maps: writes to read-only or reserved registers are
+/// ignored, software reset and reboot restore the power-on values, and the WHO_AM_I registers
+/// hold the device IDs. Sensor data is injected as queues of raw samples. The output registers
+/// present the oldest queued sample, which is consumed once the Z-axis high byte has been read,
+/// while the sensor is powered. Data ready flags and the FIFO level follow the queues.
+///
+/// Register address auto-increment follows the device: `IF_ADD_INC` in CTRL_REG8 for the
+/// accelerometer/gyroscope, and the MSB of the sub-address for the magnetometer. Since
+/// `embedded_hal::i2c::I2c` is implemented for `&mut T`, the driver can run on `&mut simulator`,
+/// so the simulator can be inspected afterwards.
+pub struct Lsm9ds1Simulator {
+    address_ag: u8,
+    address_m: u8,
+    ag: Die,
+    m: Die,
+    gyro: SampleStream,
+    accel: SampleStream,
+    mag: SampleStream,
+    temperature: i16,
+}
+
+impl Default for Lsm9ds1Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lsm9ds1Simulator {
+    /// Create a simulator responding to the default I²C addresses of `i2c::Config`.
+    pub fn new() -> Self {
+        Self::with_addresses(AddressAg::_0x6b, AddressM::_0x1e)
+    }
+
+    /// Create a simulator responding to the given I²C addresses.
+    pub fn with_addresses(address_ag: AddressAg, address_m: AddressM) -> Self {
+        Self {
+            address_ag: address_ag.addr(),
+            address_m: address_m.addr(),
+            ag: Die::new(&DEFAULTS_AG),
+            m: Die::new(&DEFAULTS_M),
+            gyro: SampleStream::new(),
+            accel: SampleStream::new(),
+            mag: SampleStream::new(),
+            temperature: 0,
+        }
+    }
+
+    /// Queue a gyroscope sample. Returns the sample if the queue is full.
+    pub fn push_gyro_sample(&mut self, sample: RawSample) -> Result<(), RawSample> {
+        self.gyro.queue.push_back(sample)
+    }
+
+    /// Queue an accelerometer sample. Returns the sample if the queue is full.
+    pub fn push_accel_sample(&mut self, sample: RawSample) -> Result<(), RawSample> {
+        self.accel.queue.push_back(sample)
+    }
+
+    /// Queue a magnetometer sample. Returns the sample if the queue is full.
+    pub fn push_mag_sample(&mut self, sample: RawSample) -> Result<(), RawSample> {
+        self.mag.queue.push_back(sample)
+    }
+
+    /// Set the raw temperature output.
+    pub fn set_temperature_raw(&mut self, raw: i16) {
+        self.temperature = raw;
+    }
+
+    /// Stored value of a register, as last written or set.
+    pub fn register(&self, component: Component, addr: u8) -> u8 {
+        self.die(component).registers[addr as usize % REGISTER_COUNT]
+    }
+
+    /// Set a register, bypassing write protection, e.g. to inject interrupt sources or status
+    /// flags.
+    pub fn set_register(&mut self, component: Component, addr: u8, value: u8) {
+        self.die_mut(component).registers[addr as usize % REGISTER_COUNT] = value;
+    }
+
+    /// Restore the power-on state of both dies, as after a brown-out. Queued samples are kept.
+    pub fn power_cycle(&mut self) {
+        self.ag.load_defaults(&DEFAULTS_AG);
+        self.m.load_defaults(&DEFAULTS_M);
+    }
+
+    fn die(&self, component: Component) -> &Die {
+        match component {
+            Component::AccelGyro => &self.ag,
+            Component::Magnetometer => &self.m,
+        }
+    }
+
+    fn die_mut(&mut self, component: Component) -> &mut Die {
+        match component {
+            Component::AccelGyro => &mut self.ag,
+            Component::Magnetometer => &mut self.m,
+        }
+    }
+
+    fn component(&self, address: u8) -> Result<Component, SimulatorError> {
+        if address == self.address_ag {
+            Ok(Component::AccelGyro)
+        } else if address == self.address_m {
+            Ok(Component::Magnetometer)
+        } else {
+            Err(SimulatorError::NoAcknowledge(address))
+        }
+    }
+
+    fn gyro_powered(&self) -> bool {
+        self.ag.get(Register::CTRL_REG1_G) >> 5 != 0
+    }
+
+    fn accel_powered(&self) -> bool {
+        self.gyro_powered() || self.ag.get(Register::CTRL_REG6_XL) >> 5 != 0
+    }
+
+    fn mag_powered(&self) -> bool {
+        self.m.get(Register::CTRL_REG3_M) & 0b10 == 0
+    }
+
+    fn set_pointer(&mut self, component: Component, sub_address: u8) {
+        let auto_increment = match component {
+            Component::AccelGyro => self.ag.get(Register::CTRL_REG8) & 0b100 != 0,
+            Component::Magnetometer => sub_address & 0x80 != 0,
+        };
+
+        let die = self.die_mut(component);
+        die.pointer = sub_address & 0x7f;
+        die.auto_increment = auto_increment;
+    }
+
+    fn advance_pointer(&mut self, component: Component) {
+        let die = self.die_mut(component);
+        if die.auto_increment {
+            die.pointer = match (component, die.pointer) {
+                // Bursts continue from the gyroscope to the accelerometer output registers, so
+                // a FIFO sample can be read in one go.
+                (Component::AccelGyro, 0x1d) => 0x28,
+                (_, pointer) => (pointer + 1) % REGISTER_COUNT as u8,
+            };
+        }
+    }
+
+    fn write_next(&mut self, component: Component, value: u8) {
+        let addr = self.die(component).pointer;
+        match component {
+            Component::AccelGyro => self.write_ag(addr, value),
+            Component::Magnetometer => self.write_m(addr, value),
+        }
+        self.advance_pointer(component);
+    }
+
+    fn read_next(&mut self, component: Component) -> u8 {
+        let addr = self.die(component).pointer;
+        let value = match component {
+            Component::AccelGyro => self.read_ag(addr),
+            Component::Magnetometer => self.read_m(addr),
+        };
+        self.advance_pointer(component);
+
+        value
+    }
+
+    fn write_ag(&mut self, addr: u8, value: u8) {
+        if !WRITABLE_AG
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&addr))
+        {
+            return;
+        }
+
+        self.ag.registers[addr as usize] = value;
+
+        // BOOT and SW_RESET restore the power-on values and clear themselves.
+        if addr == 0x22 && value & 0b1000_0001 != 0 {
+            self.ag.load_defaults(&DEFAULTS_AG);
+        }
+    }
+
+    fn write_m(&mut self, addr: u8, value: u8) {
+        if !WRITABLE_M
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&addr))
+        {
+            return;
+        }
+
+        self.m.registers[addr as usize] = value;
+
+        // REBOOT and SOFT_RST restore the power-on values and clear themselves.
+        if addr == 0x21 && value & 0b1100 != 0 {
+            self.m.load_defaults(&DEFAULTS_M);
+        }
+    }
+
+    fn read_ag(&mut self, addr: u8) -> u8 {
+        let big_endian = self.ag.get(Register::CTRL_REG8) & 0b10 != 0;
+
+        match addr {
+            0x15 => self.temperature.to_le_bytes()[0],
+            0x16 => self.temperature.to_le_bytes()[1],
+            // STATUS_REG is mirrored at both addresses.
+            0x17 | 0x27 => (self.ag.registers[addr as usize] & 0b0111_1000) | self.ag_data_ready(),
+            0x18..=0x1d => {
+                let value = self.gyro.output_byte(addr - 0x18, big_endian, [0; 3]);
+                if addr == 0x1d && self.gyro_powered() {
+                    self.gyro.advance();
+                }
+                value
+            }
+            0x28..=0x2d => {
+                let value = self.accel.output_byte(addr - 0x28, big_endian, [0; 3]);
+                if addr == 0x2d && self.accel_powered() {
+                    self.accel.advance();
+                }
+                value
+            }
+            0x2f => self.fifo_src(),
+            _ => self.ag.registers[addr as usize],
+        }
+    }
+
+    fn read_m(&mut self, addr: u8) -> u8 {
+        let big_endian = self.m.get(Register::CTRL_REG4_M) & 0b10 != 0;
+
+        match addr {
+            0x27 => {
+                let available = self.mag_powered() && !self.mag.queue.is_empty();
+                (self.m.registers[addr as usize] & 0b1111_0000)
+                    | if available { 0b1111 } else { 0b0000 }
+            }
+            0x28..=0x2d => {
+                // The hard-iron offset registers are subtracted from the output.
+                let offset = &self.m.registers[0x05..=0x0a];
+                let bias = [
+                    i16::from_le_bytes([offset[0], offset[1]]),
+                    i16::from_le_bytes([offset[2], offset[3]]),
+                    i16::from_le_bytes([offset[4], offset[5]]),
+                ];
+                let value = self.mag.output_byte(addr - 0x28, big_endian, bias);
+                if addr == 0x2d && self.mag_powered() {
+                    self.mag.advance();
+
+                    // Single conversion mode returns to power-down after one sample.
+                    if self.m.get(Register::CTRL_REG3_M) & 0b11 == 0b01 {
+                        self.m.registers[0x22] |= 0b11;
+                    }
+                }
+                value
+            }
+            _ => self.m.registers[addr as usize],
+        }
+    }
+
+    /// Data ready flags of STATUS_REG.
+    fn ag_data_ready(&self) -> u8 {
+        let gyro = self.gyro_powered() && !self.gyro.queue.is_empty();
+        let accel = self.accel_powered() && !self.accel.queue.is_empty();
+        let temperature = self.accel_powered();
+
+        (if temperature { 0b100 } else { 0b000 })
+            | (if gyro { 0b010 } else { 0b000 })
+            | (if accel { 0b001 } else { 0b000 })
+    }
+
+    /// FIFO_SRC, with the FIFO filled from the gyroscope and accelerometer queues. In FIFO mode
+    /// collection stops when the FIFO is full; only the continuous modes overwrite samples and
+    /// report an overrun.
+    fn fifo_src(&self) -> u8 {
+        let fifo_enabled = self.ag.get(Register::CTRL_REG9) & 0b10 != 0;
+        let fifo_ctrl = self.ag.get(Register::FIFO_CTRL);
+        let mode = fifo_ctrl >> 5;
+        if !fifo_enabled || mode == 0b000 {
+            return 0;
+        }
+
+        let threshold = fifo_ctrl & 0b1_1111;
+        let depth = if self.ag.get(Register::CTRL_REG9) & 0b1 != 0 {
+            threshold as usize + 1
+        } else {
+            crate::config::fifo::FIFO_DEPTH as usize
+        };
+        let queued = self.gyro.queue.len().min(self.accel.queue.len());
+        let level = queued.min(depth) as u8;
+
+        let threshold_reached = (if level >= threshold { 0b1 } else { 0b0 }) << 7;
+        let overwritten = matches!(mode, 0b100 | 0b110) && queued > depth;
+        let overrun = (if overwritten { 0b1 } else { 0b0 }) << 6;
+
+        threshold_reached | overrun | level
+    }
+}
+
+impl ErrorType for Lsm9ds1Simulator {
+    type Error = SimulatorError;
+}
+
+impl I2c for Lsm9ds1Simulator {
+    fn transaction(&mut self, address: u8, ops: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        let component = self.component(address)?;
+
+        for op in ops.iter_mut() {
+            match op {
+                Operation::Write(buf) => {
+                    if let Some((sub_address, data)) = buf.split_first() {
+                        self.set_pointer(component, *sub_address);
+                        for value in data {
+                            self.write_next(component, *value);
+                        }
+                    }
+                }
+                Operation::Read(buf) => {
+                    for value in buf.iter_mut() {
+                        *value = self.read_next(component);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for Lsm9ds1Simulator {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        ops: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, ops)
+    }
+}
+
+#[cfg(test)]
+use crate::{
+    config::fifo::{FifoConfig, FifoMode},
+    interface::{i2c::Config, I2cInterface},
+    measurements::FifoSample,
+    tests::NoDelay,
+    Lsm9ds1Builder, Lsm9ds1Error,
+};
+
+#[test]
+fn register_map() {
+    let mut sim = Lsm9ds1Simulator::new();
+
+    let mut value = [0];
+    sim.write_read(0x6b, &[0x0f], &mut value).unwrap();
+    assert_eq!(value, [DEVICE_ID_AG], "Wrong WHO_AM_I");
+
+    // WHO_AM_I is read-only, CTRL_REG1_G is writable.
+    sim.write(0x6b, &[0x0f, 0x00]).unwrap();
+    sim.write(0x6b, &[0x10, 0xc0]).unwrap();
+    assert_eq!(
+        sim.register(Component::AccelGyro, 0x0f),
+        DEVICE_ID_AG,
+        "Read-only register written"
+    );
+    assert_eq!(
+        sim.register(Component::AccelGyro, 0x10),
+        0xc0,
+        "Register not written"
+    );
+
+    // Software reset restores power-on values.
+    sim.write(0x6b, &[0x22, 0x05]).unwrap();
+    assert_eq!(
+        sim.register(Component::AccelGyro, 0x10),
+        0x00,
+        "CTRL_REG1_G not reset"
+    );
+    assert_eq!(
+        sim.register(Component::AccelGyro, 0x22),
+        0x04,
+        "SW_RESET not cleared"
+    );
+
+    // The magnetometer only increments the address if the MSB of the sub-address is set.
+    let mut values = [0; 2];
+    sim.write_read(0x1e, &[0x0f], &mut values).unwrap();
+    assert_eq!(values, [DEVICE_ID_M; 2], "Address incremented");
+    sim.write_read(0x1e, &[0x80 | 0x20], &mut values).unwrap();
+    assert_eq!(values, [0x10, 0x00], "Address not incremented");
+
+    assert_eq!(
+        sim.write(0x42, &[0x00]),
+        Err(SimulatorError::NoAcknowledge(0x42)),
+        "Unknown address acknowledged"
+    );
+}
+
+#[test]
+fn driver_against_simulator() {
+    let mut sim = Lsm9ds1Simulator::new();
+    sim.push_accel_sample(RawSample {
+        x: 0,
+        y: 0,
+        z: 16393,
+    })
+    .unwrap();
+    sim.push_mag_sample(RawSample {
+        x: 1000,
+        y: -1000,
+        z: 0,
+    })
+    .unwrap();
+    sim.set_temperature_raw(-80);
+
+    let mut device = Lsm9ds1Builder::new()
+        .with_identity_check(true)
+        .with_magnetometer_enabled(true)
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+
+    assert!(
+        device.verify_config().unwrap().is_empty(),
+        "Configuration not applied"
+    );
+    assert!(
+        device.status().unwrap().accel_data_available,
+        "Accelerometer data not available"
+    );
+
+    let acceleration = device.read_acceleration().unwrap();
+    assert!((acceleration.z - 1.0).abs() < 1e-3, "Wrong acceleration");
+    assert!(
+        !device.status().unwrap().accel_data_available,
+        "Accelerometer sample not consumed"
+    );
+
+    let field = device.read_magnetometer_raw().unwrap();
+    assert_eq!(
+        field,
+        RawSample {
+            x: 1000,
+            y: -1000,
+            z: 0
+        },
+        "Wrong magnetometer sample"
+    );
+    assert_eq!(device.temperature_c().unwrap(), 20.0, "Wrong temperature");
+
+    device.reset().unwrap();
+    assert_eq!(
+        device.verify_config().unwrap().as_slice(),
+        [
+            "accel_gyro.accel_gyro_sampling_rate",
+            "accel_gyro.accel_only_sampling_rate"
+        ],
+        "Reset not detected"
+    );
+}
+
+#[test]
+fn hard_iron_offset_against_simulator() {
+    use crate::measurements::MagneticField;
+
+    let mut sim = Lsm9ds1Simulator::new();
+    sim.push_mag_sample(RawSample {
+        x: 1000,
+        y: 0,
+        z: -1000,
+    })
+    .unwrap();
+
+    let mut device = Lsm9ds1Builder::new()
+        .with_magnetometer_enabled(true)
+        .with_hard_iron_offset(MagneticField {
+            x: 0.07,
+            y: 0.0,
+            z: -0.07,
+        })
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+
+    let offset = device.read_hard_iron_offset().unwrap();
+    assert!((offset.x - 0.07).abs() < 1e-6, "Wrong offset read back");
+    assert_eq!(
+        device.read_magnetometer_raw().unwrap(),
+        RawSample {
+            x: 500,
+            y: 0,
+            z: -500
+        },
+        "Offset not subtracted"
+    );
+
+    device
+        .set_hard_iron_offset(MagneticField::default())
+        .unwrap();
+    assert!(
+        device.verify_config().unwrap().is_empty(),
+        "Offset not recorded"
+    );
+}
+
+#[test]
+fn fifo_against_simulator() {
+    let mut sim = Lsm9ds1Simulator::new();
+    for i in 0..48 {
+        let sample = RawSample { x: i, y: 0, z: 0 };
+        sim.push_gyro_sample(sample).unwrap();
+        sim.push_accel_sample(sample).unwrap();
+    }
+
+    let mut device = Lsm9ds1Builder::new()
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+    device
+        .set_fifo_config(FifoConfig {
+            enabled: true,
+            mode: FifoMode::Fifo,
+            threshold: 16,
+            stop_on_threshold: false,
+        })
+        .unwrap();
+
+    let status = device.fifo_status().unwrap();
+    assert_eq!(status.level, 32, "Wrong FIFO level");
+    assert!(!status.overrun, "FIFO overrun reported in FIFO mode");
+    assert!(status.threshold_reached, "FIFO threshold not reported");
+
+    let mut samples = [FifoSample::default(); 8];
+    assert_eq!(device.read_fifo(&mut samples).unwrap(), 8, "Wrong count");
+    assert_eq!(
+        samples[7].acceleration.x,
+        7.0 * crate::config::accel_gyro::AccelFullScale::PlusMinus2g.sensitivity(),
+        "Wrong FIFO sample"
+    );
+
+    device
+        .set_fifo_config(FifoConfig {
+            enabled: true,
+            mode: FifoMode::Continuous,
+            threshold: 16,
+            stop_on_threshold: false,
+        })
+        .unwrap();
+    assert!(
+        device.fifo_status().unwrap().overrun,
+        "FIFO overrun not reported in continuous mode"
+    );
+}
+
+#[test]
+fn gyroscope_calibration_against_simulator() {
+    use crate::calibration::{Calibration, GYRO_CALIBRATION_VARIANCE_LIMIT};
+    use crate::config::accel_gyro::GyroFullScale;
+
+    let mut sim = Lsm9ds1Simulator::new();
+    for i in 0..30 {
+        sim.push_gyro_sample(RawSample {
+            x: 100 + i % 3,
+            y: -50,
+            z: 0,
+        })
+        .unwrap();
+    }
+
+    let calibration = {
+        let mut device = Lsm9ds1Builder::new()
+            .init_on(I2cInterface::new(&mut sim, Config::default()))
+            .expect("Error during initialization");
+
+        let calibration = device
+            .calibrate_gyroscope(&mut NoDelay, 30, GYRO_CALIBRATION_VARIANCE_LIMIT)
+            .expect("Error during calibration");
+        let sensitivity = GyroFullScale::_245dps.sensitivity();
+        assert!(
+            (calibration.bias.x - 101.0 * sensitivity).abs() < 1e-5,
+            "Wrong X bias"
+        );
+        assert!(
+            (calibration.bias.y + 50.0 * sensitivity).abs() < 1e-5,
+            "Wrong Y bias"
+        );
+
+        assert!(
+            matches!(
+                device.calibrate_gyroscope(&mut NoDelay, 1, GYRO_CALIBRATION_VARIANCE_LIMIT),
+                Err(Lsm9ds1Error::DataNotReady)
+            ),
+            "Missing samples not detected"
+        );
+        assert!(
+            device.read_angular_rate().unwrap().y.abs() < 1e-5,
+            "Bias not subtracted"
+        );
+
+        calibration
+    };
+
+    for i in 0..30 {
+        sim.push_gyro_sample(RawSample {
+            x: 100,
+            y: -50,
+            z: if i % 2 == 0 { 2000 } else { -2000 },
+        })
+        .unwrap();
+    }
+
+    let mut device = Lsm9ds1Builder::new()
+        .with_calibration(Calibration {
+            gyro: Some(calibration),
+            ..Default::default()
+        })
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+
+    assert!(
+        matches!(
+            device.calibrate_gyroscope(&mut NoDelay, 30, GYRO_CALIBRATION_VARIANCE_LIMIT),
+            Err(Lsm9ds1Error::MotionDetected)
+        ),
+        "Motion not detected"
+    );
+    assert_eq!(
+        device.calibration().gyro,
+        Some(calibration),
+        "Calibration replaced by rejected run"
+    );
+}
+
+#[test]
+fn accelerometer_calibration_against_simulator() {
+    use crate::calibration::Orientation;
+    use crate::calibration::{
+        AccelCalibrator, Calibration, ACCEL_CALIBRATION_SAMPLES, ACCEL_CALIBRATION_VARIANCE_LIMIT,
+    };
+    use crate::config::accel_gyro::AccelFullScale;
+
+    let sensitivity = AccelFullScale::PlusMinus2g.sensitivity();
+    // 2% gain error on X, 40mg offset on Z.
+    let raw = |gravity: [f32; 3]| RawSample {
+        x: (gravity[0] * 1.02 / sensitivity) as i16,
+        y: (gravity[1] / sensitivity) as i16,
+        z: ((gravity[2] + 0.04) / sensitivity) as i16,
+    };
+
+    let mut sim = Lsm9ds1Simulator::new();
+    let mut calibrator = AccelCalibrator::new();
+    while let Some(orientation) = calibrator.next_missing() {
+        let gravity = match orientation {
+            Orientation::XUp => [1.0, 0.0, 0.0],
+            Orientation::XDown => [-1.0, 0.0, 0.0],
+            Orientation::YUp => [0.0, 1.0, 0.0],
+            Orientation::YDown => [0.0, -1.0, 0.0],
+            Orientation::ZUp => [0.0, 0.0, 1.0],
+            Orientation::ZDown => [0.0, 0.0, -1.0],
+        };
+        for _ in 0..ACCEL_CALIBRATION_SAMPLES {
+            sim.push_accel_sample(raw(gravity)).unwrap();
+        }
+
+        let mut device = Lsm9ds1Builder::new()
+            .init_on(I2cInterface::new(&mut sim, Config::default()))
+            .expect("Error during initialization");
+        let acceleration = device
+            .average_acceleration(
+                &mut NoDelay,
+                ACCEL_CALIBRATION_SAMPLES,
+                ACCEL_CALIBRATION_VARIANCE_LIMIT,
+            )
+            .expect("Error while recording orientation");
+        assert!(
+            calibrator.record(orientation, acceleration),
+            "{orientation:?} rejected"
+        );
+    }
+
+    let calibration = calibrator.solve().expect("No calibration");
+    sim.push_accel_sample(raw([0.6, 0.0, -0.8])).unwrap();
+
+    let mut device = Lsm9ds1Builder::new()
+        .with_calibration(Calibration {
+            accel: Some(calibration),
+            ..Default::default()
+        })
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+
+    let (x, y, z) = device.get_accelerometer_data().unwrap();
+    assert!((x - 0.6).abs() < 1e-3, "Gain error not corrected");
+    assert!(y.abs() < 1e-3, "Wrong Y acceleration");
+    assert!((z + 0.8).abs() < 1e-3, "Offset not corrected");
+}
+
+#[test]
+fn gyro_temperature_model_against_simulator() {
+    use crate::calibration::temperature::{GyroTemperatureFitter, ModelOrder};
+    use crate::calibration::{Calibration, GYRO_CALIBRATION_VARIANCE_LIMIT};
+
+    // The X bias rises by 4 LSB/°C, and temperature by 16 LSB/°C.
+    let bias = |temperature_raw: i16| RawSample {
+        x: 100 + temperature_raw / 4,
+        y: -50,
+        z: 0,
+    };
+
+    let mut sim = Lsm9ds1Simulator::new();
+    let mut fitter = GyroTemperatureFitter::new(ModelOrder::Linear);
+    for temperature_raw in [-320, 0, 320] {
+        sim.set_temperature_raw(temperature_raw);
+        for _ in 0..8 {
+            sim.push_gyro_sample(bias(temperature_raw)).unwrap();
+        }
+
+        let mut device = Lsm9ds1Builder::new()
+            .init_on(I2cInterface::new(&mut sim, Config::default()))
+            .expect("Error during initialization");
+        let temperature_c = device
+            .record_gyro_bias(
+                &mut fitter,
+                &mut NoDelay,
+                8,
+                GYRO_CALIBRATION_VARIANCE_LIMIT,
+            )
+            .expect("Error while recording bias");
+        assert_eq!(
+            temperature_c,
+            25.0 + temperature_raw as f32 / 16.0,
+            "Wrong temperature"
+        );
+    }
+
+    let model = fitter.fit().expect("No model");
+    sim.set_temperature_raw(160);
+    sim.push_gyro_sample(bias(160)).unwrap();
+
+    let mut device = Lsm9ds1Builder::new()
+        .with_calibration(Calibration {
+            gyro_temperature: Some(model),
+            ..Default::default()
+        })
+        .init_on(I2cInterface::new(&mut sim, Config::default()))
+        .expect("Error during initialization");
+
+    let rate = device.read_angular_rate().unwrap();
+    for value in [rate.x, rate.y, rate.z] {
+        assert!(value.abs() < 1e-4, "Bias not compensated: {rate:?}");
+    }
+}
@@ -0,0 +1,53 @@
+use crate::config::activity::ActivityState;
+
+/// Accelerometer/Gyroscope status, as reported by the `STATUS_REG` register.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    /// Accelerometer interrupt generator event occurred.
+    pub accel_interrupt: bool,
+    /// Gyroscope interrupt generator event occurred.
+    pub gyro_interrupt: bool,
+    /// Inactivity detected.
+    pub inactive: bool,
+    /// Boot is running.
+    pub boot_running: bool,
+    /// New temperature data available.
+    pub temperature_data_available: bool,
+    /// New gyroscope data available.
+    pub gyro_data_available: bool,
+    /// New accelerometer data available.
+    pub accel_data_available: bool,
+}
+
+impl Status {
+    /// Activity state of the device.
+    pub fn activity_state(&self) -> ActivityState {
+        if self.inactive {
+            ActivityState::Inactive
+        } else {
+            ActivityState::Active
+        }
+    }
+}
+
+#[test]
+fn status_reg() {
+    let status = crate::registers::status_reg(0b0101_0011);
+
+    assert_eq!(
+        status,
+        Status {
+            accel_interrupt: true,
+            inactive: true,
+            gyro_data_available: true,
+            accel_data_available: true,
+            ..Default::default()
+        },
+        "Wrong decoded STATUS_REG value"
+    );
+    assert_eq!(
+        status.activity_state(),
+        ActivityState::Inactive,
+        "Wrong activity state"
+    );
+}
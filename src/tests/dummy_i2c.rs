@@ -8,7 +8,7 @@ pub struct DummyI2c {
 }
 
 /// Errors reported by the dummy I2C bus.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum DummyError {}
 
 impl i2c::Error for DummyError {
@@ -80,6 +80,17 @@ impl I2c<SevenBitAddress> for DummyI2c {
     }
 }
 
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c<SevenBitAddress> for DummyI2c {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        ops: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, ops)
+    }
+}
+
 #[test]
 fn write_to_dummy_i2c() {
     let mut i2c = DummyI2c::new();
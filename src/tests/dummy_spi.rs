@@ -0,0 +1,116 @@
+use embedded_hal::spi::{self, ErrorKind, ErrorType, Operation, SpiDevice};
+
+/// A dummy SPI device that sends preset data and records what is sent to it.
+pub struct DummySpi {
+    rx_buffer: Vec<u8>,
+    tx_buffer: Vec<u8>,
+}
+
+/// Errors reported by the dummy SPI device.
+#[derive(Debug)]
+pub enum DummyError {}
+
+impl spi::Error for DummyError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl DummySpi {
+    /// Create a new dummy device.
+    pub fn new() -> Self {
+        Self {
+            rx_buffer: Vec::new(),
+            tx_buffer: vec![0u8],
+        }
+    }
+
+    /// Create a new dummy device with preset tx data.
+    pub fn new_with_tx(tx: &[u8]) -> Self {
+        let mut spi = Self::new();
+        spi.set_tx(tx);
+        spi
+    }
+
+    /// Bytes written during the last transaction.
+    pub fn rx(&self) -> Vec<u8> {
+        self.rx_buffer.clone()
+    }
+
+    /// Set data to be sent by the device.
+    pub fn set_tx(&mut self, bytes: &[u8]) {
+        self.tx_buffer = bytes.to_owned();
+    }
+
+    fn fill(&self, buf: &mut [u8]) {
+        let tx_iter = self.tx_buffer.iter().cycle();
+        for (buf_byte, tx_byte) in buf.iter_mut().zip(tx_iter) {
+            *buf_byte = *tx_byte;
+        }
+    }
+}
+
+impl ErrorType for DummySpi {
+    type Error = DummyError;
+}
+
+impl SpiDevice for DummySpi {
+    fn transaction(&mut self, ops: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.rx_buffer.clear();
+
+        for op in ops.iter_mut() {
+            match op {
+                Operation::Read(buf) => self.fill(buf),
+                Operation::Write(buf) => self.rx_buffer.extend_from_slice(buf),
+                Operation::Transfer(read, write) => {
+                    self.rx_buffer.extend_from_slice(write);
+                    self.fill(read);
+                }
+                Operation::TransferInPlace(buf) => {
+                    self.rx_buffer.extend_from_slice(buf);
+                    self.fill(buf);
+                }
+                Operation::DelayNs(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice for DummySpi {
+    async fn transaction(&mut self, ops: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        SpiDevice::transaction(self, ops)
+    }
+}
+
+#[test]
+fn write_to_dummy_spi() {
+    let mut spi = DummySpi::new();
+
+    let msg = vec![0xbb, 0xcc];
+    spi.write(&msg).expect("Error during SPI write");
+
+    assert_eq!(
+        spi.rx(),
+        msg,
+        "Data received by dummy doesn't match sent message"
+    );
+}
+
+#[test]
+fn write_read_dummy_spi() {
+    let mut spi = DummySpi::new_with_tx(&[1, 2, 3]);
+
+    let mut buf = vec![0, 0, 0, 0, 0];
+    spi.transaction(&mut [Operation::Write(&[0xaa]), Operation::Read(&mut buf)])
+        .expect("Error during SPI transaction");
+
+    assert_eq!(spi.rx(), vec![0xaa], "Data received by dummy doesn't match");
+    assert_eq!(
+        buf,
+        vec![1, 2, 3, 1, 2],
+        "Data read from dummy doesn't match set TX buffer"
+    );
+}
@@ -0,0 +1,144 @@
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+
+use super::dummy_i2c::DummyI2c;
+
+/// An I2C bus that wraps another bus and injects faults.
+pub struct FaultyI2c<I2C = DummyI2c> {
+    bus: I2C,
+    transactions: usize,
+    nack_address: Option<u8>,
+    fail_transaction: Option<usize>,
+    timeout_after: Option<usize>,
+    corrupt_mask: u8,
+}
+
+/// Errors reported by the faulty bus.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FaultyError<E> {
+    /// Error of the wrapped bus.
+    Bus(E),
+    /// The address was not acknowledged.
+    NoAcknowledge(u8),
+    /// The transaction failed.
+    Failed,
+    /// The transaction timed out.
+    Timeout,
+}
+
+impl<E: i2c::Error> i2c::Error for FaultyError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Bus(e) => e.kind(),
+            Self::NoAcknowledge(_) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            Self::Failed => ErrorKind::Bus,
+            Self::Timeout => ErrorKind::Other,
+        }
+    }
+}
+
+impl FaultyI2c {
+    /// Create a faulty dummy bus with preset tx data.
+    pub fn new_with_tx(tx: &[u8]) -> Self {
+        Self::new(DummyI2c::new_with_tx(tx))
+    }
+}
+
+impl<I2C> FaultyI2c<I2C> {
+    /// Wrap a bus, without injecting any faults.
+    pub fn new(bus: I2C) -> Self {
+        Self {
+            bus,
+            transactions: 0,
+            nack_address: None,
+            fail_transaction: None,
+            timeout_after: None,
+            corrupt_mask: 0,
+        }
+    }
+
+    /// Don't acknowledge any transaction to `address`.
+    pub fn nack_address(mut self, address: u8) -> Self {
+        self.nack_address = Some(address);
+        self
+    }
+
+    /// Fail the transaction with the given zero based index.
+    pub fn fail_transaction(mut self, index: usize) -> Self {
+        self.fail_transaction = Some(index);
+        self
+    }
+
+    /// Time out all transactions from the given zero based index on, like a hung bus.
+    pub fn timeout_after(mut self, index: usize) -> Self {
+        self.timeout_after = Some(index);
+        self
+    }
+
+    /// Flip the given bits in every byte read from the bus.
+    pub fn corrupt_reads(mut self, mask: u8) -> Self {
+        self.corrupt_mask = mask;
+        self
+    }
+
+    /// Number of transactions attempted so far.
+    pub fn transactions(&self) -> usize {
+        self.transactions
+    }
+}
+
+impl<I2C: I2c> ErrorType for FaultyI2c<I2C> {
+    type Error = FaultyError<I2C::Error>;
+}
+
+impl<I2C: I2c> I2c for FaultyI2c<I2C> {
+    fn transaction(&mut self, address: u8, ops: &mut [Operation]) -> Result<(), Self::Error> {
+        let index = self.transactions;
+        self.transactions += 1;
+
+        if self.nack_address == Some(address) {
+            return Err(FaultyError::NoAcknowledge(address));
+        }
+        if self.fail_transaction == Some(index) {
+            return Err(FaultyError::Failed);
+        }
+        if self.timeout_after.is_some_and(|first| index >= first) {
+            return Err(FaultyError::Timeout);
+        }
+
+        self.bus
+            .transaction(address, ops)
+            .map_err(FaultyError::Bus)?;
+
+        for op in ops.iter_mut() {
+            if let Operation::Read(buf) = op {
+                for byte in buf.iter_mut() {
+                    *byte ^= self.corrupt_mask;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn inject_faults() {
+    let mut i2c = FaultyI2c::new_with_tx(&[0x0f])
+        .nack_address(0x1e)
+        .fail_transaction(1)
+        .timeout_after(3)
+        .corrupt_reads(0xf0);
+
+    let mut buf = [0];
+    i2c.read(0x6b, &mut buf).expect("Unexpected fault");
+    assert_eq!(buf, [0xff], "Read not corrupted");
+
+    assert_eq!(i2c.read(0x6b, &mut buf), Err(FaultyError::Failed));
+    assert_eq!(
+        i2c.read(0x1e, &mut buf),
+        Err(FaultyError::NoAcknowledge(0x1e))
+    );
+    assert_eq!(i2c.read(0x6b, &mut buf), Err(FaultyError::Timeout));
+    assert_eq!(i2c.read(0x6b, &mut buf), Err(FaultyError::Timeout));
+    assert_eq!(i2c.transactions(), 5, "Wrong transaction count");
+}
@@ -1,2 +1,27 @@
 pub mod dummy_i2c;
+pub mod dummy_spi;
+pub mod faulty_i2c;
 
+use embedded_hal::delay::DelayNs;
+
+/// A delay that returns immediately.
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Run a future to completion. Dummy buses never return `Pending`, so no real executor is needed.
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}